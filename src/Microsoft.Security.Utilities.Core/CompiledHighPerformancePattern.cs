@@ -167,6 +167,10 @@ internal sealed partial class CompiledHighPerformancePattern
     ///
     /// On .NET 8+, this input is further processed by the regex source generator
     /// to pre-compile the regexes into C# source code.
+    ///
+    /// The Rust crate mirrors the generated table in high_performance_scanner.rs
+    /// and a Rust unit test fails if the two drift apart, so update both when
+    /// adding or changing a pattern.
     /// </summary>
     internal static string GenerateAdditionalCode()
     {
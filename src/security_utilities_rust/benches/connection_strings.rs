@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{
+    ScanEngine, ScanOptions,
+};
+
+fn connection_strings_benchmark(c: &mut Criterion) {
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    /* Code and prose full of "key" without any connection strings */
+    let key_heavy = "const apiKey = config.key; // the monkey keyed in a key, see keyboard shortcuts and hotkeys\n"
+        .repeat(1000);
+
+    c.bench_function("connection strings scan key heavy text", |b| {
+        b.iter(|| {
+            engine.find_matches(key_heavy.as_bytes(), false)
+        })
+    });
+
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+
+    c.bench_function("connection strings scan utf8", |b| {
+        b.iter(|| {
+            engine.find_matches(data_utf8.as_bytes(), false)
+        })
+    });
+}
+
+criterion_group! {
+    name = connection_strings;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = connection_strings_benchmark,
+}
+
+criterion_main!(connection_strings);
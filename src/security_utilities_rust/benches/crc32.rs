@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::crc32::compute_checksum;
+
+fn crc32_benchmark(c: &mut Criterion) {
+    let data_0 = [0u8; 0];
+    let data_28 = [0u8; 28];
+    let data_1024 = [0u8; 1024];
+    let data_65535 = [0u8; 65535];
+
+    c.bench_function("crc32 0", |b| b.iter(|| compute_checksum(&data_0)))
+        .bench_function("crc32 28", |b| b.iter(|| compute_checksum(&data_28)))
+        .bench_function("crc32 1024", |b| b.iter(|| compute_checksum(&data_1024)))
+        .bench_function("crc32 65535", |b| b.iter(|| compute_checksum(&data_65535)));
+}
+
+criterion_group! {
+    name = crc32;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = crc32_benchmark,
+}
+
+criterion_main!(crc32);
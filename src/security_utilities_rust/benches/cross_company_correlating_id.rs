@@ -4,13 +4,13 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use microsoft_security_utilities_core::microsoft_security_utilities_core::cross_company_correlating_id::generate_cross_company_correlating_id;
 
 fn c3id_benchmark(c: &mut Criterion) {
-    let data_0: String = std::iter::repeat('a').take(0).collect();
-    let data_8: String = std::iter::repeat('a').take(8).collect();
-    let data_16: String = std::iter::repeat('a').take(16).collect();
-    let data_32: String = std::iter::repeat('a').take(32).collect();
-    let data_64: String = std::iter::repeat('a').take(64).collect();
-    let data_88: String = std::iter::repeat('a').take(64).collect();
-    let data_1024: String = std::iter::repeat('a').take(1024).collect();
+    let data_0: String = "a".repeat(0);
+    let data_8: String = "a".repeat(8);
+    let data_16: String = "a".repeat(16);
+    let data_32: String = "a".repeat(32);
+    let data_64: String = "a".repeat(64);
+    let data_88: String = "a".repeat(64);
+    let data_1024: String = "a".repeat(1024);
 
     let c3id = |data| generate_cross_company_correlating_id(data);
 
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::high_performance_scanner::HighPerformanceScanner;
+
+fn high_performance_scanner_benchmark(c: &mut Criterion) {
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+    let scanner = HighPerformanceScanner::default();
+
+    c.bench_function("high performance scan utf8", |b| {
+        b.iter(|| {
+            scanner.scan(data_utf8.as_bytes(), false)
+        })
+    });
+}
+
+criterion_group! {
+    name = high_performance_scans;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = high_performance_scanner_benchmark,
+}
+
+criterion_main!(high_performance_scans);
@@ -16,6 +16,20 @@ fn id_scans_benchmark(c: &mut Criterion) {
             engine.parse_bytes(&mut state, data_utf8.as_bytes());
         })
     });
+
+    let tables = engine.to_bytes().unwrap();
+
+    c.bench_function("engine new", |b| {
+        b.iter(|| {
+            ScanEngine::new(Default::default())
+        })
+    });
+
+    c.bench_function("engine from_bytes", |b| {
+        b.iter(|| {
+            ScanEngine::from_bytes(&tables).unwrap()
+        })
+    });
 }
 
 criterion_group! {
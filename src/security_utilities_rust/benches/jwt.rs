@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{
+    ScanEngine, ScanOptions,
+};
+
+fn jwt_benchmark(c: &mut Criterion) {
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+    let engine = ScanEngine::new(ScanOptions::empty().with_jwt());
+
+    c.bench_function("jwt scan utf8", |b| {
+        b.iter(|| {
+            engine.find_matches(data_utf8.as_bytes(), false)
+        })
+    });
+}
+
+criterion_group! {
+    name = jwt;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = jwt_benchmark,
+}
+
+criterion_main!(jwt);
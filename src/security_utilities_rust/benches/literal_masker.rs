@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::literal_masker::LiteralMasker;
+
+fn literal_masker_benchmark(c: &mut Criterion) {
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+
+    let mut masker = LiteralMasker::default();
+
+    for i in 0..10_000 {
+        masker.add_value(&format!("secret-value-{:05}", i));
+    }
+
+    c.bench_function("literal mask utf8", |b| {
+        b.iter(|| {
+            masker.mask(data_utf8)
+        })
+    });
+}
+
+criterion_group! {
+    name = literal_masker;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = literal_masker_benchmark,
+}
+
+criterion_main!(literal_masker);
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::log_redaction::LogScrubber;
+
+fn log_redaction_benchmark(c: &mut Criterion) {
+    let scrubber = LogScrubber::new(Arc::new(ScanEngine::new(ScanOptions::default())));
+
+    let clean = "2024-11-19T10:00:00Z INFO request completed status=200 elapsed_ms=12 path=/api/v1/items";
+    let secret = "2024-11-19T10:00:00Z WARN retrying with key=HHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHJQQJ99AEAAAAAAAAAAAAAAAAAZFU03Ml";
+
+    c.bench_function("log scrub clean line", |b| {
+        b.iter(|| {
+            scrubber.scrub(clean).len()
+        })
+    });
+
+    c.bench_function("log scrub line with secret", |b| {
+        b.iter(|| {
+            scrubber.scrub(secret).len()
+        })
+    });
+}
+
+criterion_group! {
+    name = log_redaction;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = log_redaction_benchmark,
+}
+
+criterion_main!(log_redaction);
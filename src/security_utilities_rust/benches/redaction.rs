@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{
+    ScanEngine, ScanOptions,
+};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::redaction;
+
+fn redaction_benchmark(c: &mut Criterion) {
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+    let engine = ScanEngine::new(ScanOptions::default().with_url_credentials());
+
+    c.bench_function("mask utf8", |b| {
+        b.iter(|| {
+            redaction::mask(&engine, data_utf8)
+        })
+    });
+}
+
+criterion_group! {
+    name = redaction;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = redaction_benchmark,
+}
+
+criterion_main!(redaction);
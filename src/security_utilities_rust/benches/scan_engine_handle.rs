@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::scan_engine_handle::ScanEngineHandle;
+
+fn scan_engine_handle_benchmark(c: &mut Criterion) {
+    let data_utf8 = include_str!("../test_files/bench_data.utf8");
+    let handle = ScanEngineHandle::default();
+
+    c.bench_function("pinned scan utf8", |b| {
+        b.iter(|| {
+            let mut scan = handle.scan();
+            scan.parse_bytes(data_utf8.as_bytes());
+            scan.has_possible_matches()
+        })
+    });
+}
+
+criterion_group! {
+    name = scan_engine_handle;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = scan_engine_handle_benchmark,
+}
+
+criterion_main!(scan_engine_handle);
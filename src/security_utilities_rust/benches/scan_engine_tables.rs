@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{
+    ScanDefinition, ScanEngine, ScanOptions,
+};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::validators::ValidatorSpec;
+
+const RULE_COUNT: usize = 500;
+
+/* Rules as they would come from configuration, a signature and a pattern each */
+fn rules() -> Vec<(&'static str, String, String)> {
+    (0..RULE_COUNT)
+        .map(|i| {
+            let name: &'static str = Box::leak(format!("BENCH/{:03}", i).into_boxed_str());
+            let sig = format!("{:04}", i);
+            let pattern = format!("^.{{4}}[0-9a-z]{{{}}}(?:[0-9A-F]{{2}}|==)?[a-z]*", 20 + i % 40);
+
+            (name, sig, pattern)
+        })
+        .collect()
+}
+
+fn build(rules: &[(&'static str, String, String)]) -> ScanEngine {
+    let options = rules.iter().fold(ScanOptions::empty(), |options, (name, sig, pattern)| {
+        let spec: ValidatorSpec = pattern.parse().unwrap();
+
+        options.with_definition(ScanDefinition::from_spec(name, sig.as_bytes(), sig.as_bytes()[3], 4, spec))
+    });
+
+    ScanEngine::new(options)
+}
+
+/* Every built-in rule, including those with contextual validators */
+fn builtin_options() -> ScanOptions {
+    ScanOptions::default()
+        .with_high_performance_patterns()
+        .with_jwt()
+        .with_url_credentials()
+        .with_connection_strings()
+        .with_ado_legacy_pats()
+        .with_package_tokens()
+        .with_loose_credentials()
+        .with_pkcs12()
+        .with_pem_private_keys()
+        .with_unclassified(Default::default())
+}
+
+fn scan_engine_tables_benchmark(c: &mut Criterion) {
+    let rules = rules();
+    let bytes = build(&rules).to_bytes().unwrap();
+
+    c.bench_function("scan engine startup from rules", |b| {
+        b.iter(|| {
+            build(&rules)
+        })
+    });
+
+    c.bench_function("scan engine startup from tables", |b| {
+        b.iter(|| {
+            ScanEngine::from_bytes(&bytes).unwrap()
+        })
+    });
+
+    let bytes = ScanEngine::new(builtin_options()).to_bytes().unwrap();
+
+    c.bench_function("scan engine startup from built-in rules", |b| {
+        b.iter(|| {
+            ScanEngine::new(builtin_options())
+        })
+    });
+
+    c.bench_function("scan engine startup from built-in tables", |b| {
+        b.iter(|| {
+            ScanEngine::from_bytes(&bytes).unwrap()
+        })
+    });
+}
+
+criterion_group! {
+    name = scan_engine_tables;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = scan_engine_tables_benchmark,
+}
+
+criterion_main!(scan_engine_tables);
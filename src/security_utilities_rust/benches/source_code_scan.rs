@@ -0,0 +1,59 @@
+use std::path::Path;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{
+    ScanEngine, ScanOptions,
+};
+
+/* Appends every .cs and .rs file under dir, skipping build output */
+fn read_sources(
+    dir: &Path,
+    out: &mut Vec<u8>) {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap().to_string_lossy();
+
+        if path.is_dir() {
+            if name != "target" && name != "bin" && name != "obj" && !name.starts_with('.') {
+                read_sources(&path, out);
+            }
+        } else if name.ends_with(".cs") || name.ends_with(".rs") {
+            out.extend_from_slice(&std::fs::read(&path).unwrap());
+        }
+    }
+}
+
+fn source_code_scan_benchmark(c: &mut Criterion) {
+    /* Ordinary source text without secrets, the sources of this repo */
+    let mut sources = Vec::new();
+    read_sources(&Path::new(env!("CARGO_MANIFEST_DIR")).join(".."), &mut sources);
+
+    let engines = [
+        ("default", ScanEngine::new(ScanOptions::default())),
+        ("default with connection strings", ScanEngine::new(ScanOptions::default().with_connection_strings())),
+        ("default with loose credentials", ScanEngine::new(ScanOptions::default().with_loose_credentials())),
+    ];
+
+    for (name, engine) in engines {
+        c.bench_function(&format!("source code scan {}", name), |b| {
+            b.iter(|| {
+                engine.find_matches(&sources, false)
+            })
+        });
+    }
+}
+
+criterion_group! {
+    name = source_code_scan;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = source_code_scan_benchmark,
+}
+
+criterion_main!(source_code_scan);
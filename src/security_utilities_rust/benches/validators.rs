@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::validators::ValidatorSpec;
+
+fn validators_benchmark(c: &mut Criterion) {
+    let spec: ValidatorSpec = r"^[a-zA-Z0-9]{52}JQQJ9(?:9|D|H)[a-zA-Z0-9][A-L][a-zA-Z0-9]{16}[A-Za-z][a-zA-Z0-9]{7}(?:[a-zA-Z0-9]{2}==)?"
+        .parse()
+        .unwrap();
+    let data = b"6666666666666666666666666666666666666666666666666666JQQJ9DAEAAAAAAAAAAAAAAAAAZFUrS9sdA==";
+
+    c.bench_function("validator spec match", |b| {
+        b.iter(|| {
+            spec.match_len(data)
+        })
+    });
+}
+
+criterion_group! {
+    name = validators;
+    config = Criterion::default().warm_up_time(Duration::from_millis(500));
+    targets = validators_benchmark,
+}
+
+criterion_main!(validators);
@@ -0,0 +1,162 @@
+#![cfg(test)]
+
+use crate::microsoft_security_utilities_core::ado_pat::{
+    self, ADO_APPLICATION_PAT_CHECKSUM_SEED, ADO_LEGACY_PAT_RULE_ID, ADO_USER_PAT_CHECKSUM_SEED,
+};
+use crate::microsoft_security_utilities_core::crc32;
+use crate::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions, ScanState};
+
+/// Builds a legacy PAT around 28 token bytes, the way Azure DevOps does.
+fn generate_pat(
+    token_bytes: &[u8; 28],
+    seed: u32) -> String {
+    let checksum = (crc32::compute_checksum(token_bytes) ^ seed).to_le_bytes();
+
+    let mut bytes = [0u8; 32];
+    bytes[0..2].copy_from_slice(&token_bytes[0..2]);
+    bytes[3..7].copy_from_slice(&token_bytes[2..6]);
+    bytes[9..22].copy_from_slice(&token_bytes[6..19]);
+    bytes[23..32].copy_from_slice(&token_bytes[19..28]);
+    bytes[22] = checksum[0];
+    bytes[8] = checksum[1];
+    bytes[7] = checksum[2];
+    bytes[2] = checksum[3];
+
+    /* Base32, the last character has 4 bits of padding */
+    let alphabet = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut pat = String::new();
+    let mut bits = 0u64;
+    let mut bit_count = 0;
+
+    for b in bytes {
+        bits = bits << 8 | b as u64;
+        bit_count += 8;
+
+        while bit_count >= 5 {
+            bit_count -= 5;
+            pat.push(alphabet[((bits >> bit_count) & 31) as usize] as char);
+        }
+    }
+
+    pat.push(alphabet[((bits << (5 - bit_count)) & 31) as usize] as char);
+    pat
+}
+
+fn user_pat() -> String {
+    let mut token_bytes = [0u8; 28];
+
+    for (i, b) in token_bytes.iter_mut().enumerate() {
+        *b = (i as u8).wrapping_mul(37).wrapping_add(11);
+    }
+
+    generate_pat(&token_bytes, ADO_USER_PAT_CHECKSUM_SEED)
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+}
+
+#[test]
+fn ado_pat_checksum() {
+    let pat = user_pat();
+    assert_eq!(52, pat.len());
+    assert!(ado_pat::is_checksum_valid(pat.as_bytes()));
+    assert!(ado_pat::is_checksum_valid(pat.to_uppercase().as_bytes()));
+
+    let app_pat = generate_pat(&[7; 28], ADO_APPLICATION_PAT_CHECKSUM_SEED);
+    assert!(ado_pat::is_checksum_valid(app_pat.as_bytes()));
+
+    let other_pat = generate_pat(&[7; 28], 0x12345678);
+    assert!(!ado_pat::is_checksum_valid(other_pat.as_bytes()));
+
+    // Any changed character breaks the checksum
+    for i in 0..51 {
+        let mut corrupt = pat.clone().into_bytes();
+        corrupt[i] = if corrupt[i] == b'a' { b'b' } else { b'a' };
+        assert!(!ado_pat::is_checksum_valid(&corrupt), "Index {}", i);
+    }
+
+    // Invalid base32 and lengths, like the .NET tests
+    assert!(!ado_pat::is_checksum_valid(b"=22222222222222222222222222"));
+    assert!(!ado_pat::is_checksum_valid(format!("={}", &pat[1..]).as_bytes()));
+    assert!(!ado_pat::is_checksum_valid(&pat.as_bytes()[1..]));
+    assert!(!ado_pat::is_checksum_valid(b""));
+}
+
+#[test]
+fn ado_pat_scan() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_ado_legacy_pats());
+    let pat = user_pat();
+
+    let cases = [
+        (pat.clone(), Some(0)),
+        (format!("pat: {}\n", pat), Some(5)),
+        (format!("\"token\":\"{}\"", pat), Some(9)),
+        (format!("ADO{}", pat), Some(3)),
+        (format!("{}_", pat), Some(0)),
+        // Longer runs are a different token.
+        (format!("a{}", pat), None),
+        (format!("{}2", pat), None),
+        // Random lowercase characters.
+        ("abcdefghijklmnopqrstuvwxyz234567abcdefghijklmnopqrst".to_string(), None),
+        (pat[1..].to_string(), None),
+    ];
+
+    for (i, (case, expected)) in cases.iter().enumerate() {
+        let matches = engine.find_matches(case.as_bytes(), true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "Case {}", i);
+
+        if let Some(start) = expected {
+            assert_eq!(ADO_LEGACY_PAT_RULE_ID, matches[0].name(), "Case {}: Id", i);
+            assert_eq!(*start as u64, matches[0].start(), "Case {}: Start", i);
+            assert_eq!(pat, matches[0].text(), "Case {}: Text", i);
+        }
+
+        // UTF16 LE
+        let matches = engine.find_matches(&utf16(case), true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "UTF16 Case {}", i);
+
+        if let Some(start) = expected {
+            assert_eq!(*start as u64 * 2, matches[0].start(), "UTF16 Case {}: Start", i);
+            assert_eq!(104, matches[0].len(), "UTF16 Case {}: Len", i);
+            assert_eq!(pat, matches[0].text(), "UTF16 Case {}: Text", i);
+        }
+    }
+}
+
+#[test]
+fn ado_pat_scan_chunks() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_ado_legacy_pats());
+    let pat = user_pat();
+
+    for data in [format!("x = {};", pat).into_bytes(), utf16(&format!("x = {};", pat))] {
+        for split in 0..data.len() {
+            let mut state = ScanState::default();
+            engine.parse_bytes(&mut state, &data[..split]);
+            engine.parse_bytes(&mut state, &data[split..]);
+
+            let matches: Vec<_> = state
+                .possible_matches()
+                .iter()
+                .filter_map(|check| check.matches_bytes(&data[check.window_start() as usize..], true))
+                .collect();
+
+            assert_eq!(1, matches.len(), "Split {}", split);
+            assert_eq!(pat, matches[0].text(), "Split {}", split);
+        }
+    }
+}
+
+#[test]
+fn ado_pat_with_other_rules() {
+    let engine = ScanEngine::new(ScanOptions::default().with_ado_legacy_pats());
+    let pat = user_pat();
+
+    let matches = engine.find_matches(format!("{} {}", pat, pat).as_bytes(), false);
+    assert_eq!(vec![0, 53], matches.iter().map(|m| m.start()).collect::<Vec<_>>());
+
+    // Built-in rules are rebuilt from serialized tables
+    let loaded = ScanEngine::from_bytes(&engine.to_bytes().unwrap()).unwrap();
+    let matches = loaded.find_matches(format!("{} {}", pat, pat).as_bytes(), false);
+    assert_eq!(vec![0, 53], matches.iter().map(|m| m.start()).collect::<Vec<_>>());
+}
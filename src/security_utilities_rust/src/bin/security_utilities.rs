@@ -0,0 +1,176 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use microsoft_security_utilities_core::microsoft_security_utilities_core::file_redaction::{self, FileRedactionOptions};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions};
+use microsoft_security_utilities_core::microsoft_security_utilities_core::redaction::RedactionTemplate;
+
+const USAGE: &str = "\
+Usage: security_utilities redact --input <path> [options]
+
+Redacts secrets from a file, or every file in a directory, in place.
+
+Options:
+  --input <path>       File or directory to redact
+  --template <text>    Redaction token template, such as {id}:{c3id}
+  --dry-run            Print a diff of what would be redacted, including
+                       the secrets, without changing anything
+  --backup             Keep the original of each redacted file as <file>.bak,
+                       failing files whose backup already exists
+  --force              Also redact binary files
+";
+
+struct RedactArgs {
+    input: PathBuf,
+    options: FileRedactionOptions,
+}
+
+fn parse_redact_args(args: &[String]) -> Result<RedactArgs, String> {
+    let mut input = None;
+    let mut options = FileRedactionOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => {
+                input = Some(PathBuf::from(args.next().ok_or("--input needs a path")?));
+            },
+            "--template" => {
+                let template = args.next().ok_or("--template needs a template")?;
+                let template: RedactionTemplate = template.parse().map_err(|e| format!("{}", e))?;
+
+                options = options.with_template(template);
+            },
+            "--dry-run" => { options = options.with_dry_run(true); },
+            "--backup" => { options = options.with_backup(true); },
+            "--force" => { options = options.with_force(true); },
+            _ => { return Err(format!("Unknown option '{}'", arg)); },
+        }
+    }
+
+    Ok(RedactArgs {
+        input: input.ok_or("--input is required")?,
+        options,
+    })
+}
+
+/* The entries of the directory at path in order, None if it's a file */
+fn dir_entries(path: &Path) -> std::io::Result<Option<Vec<PathBuf>>> {
+    if !fs::metadata(path)?.is_dir() {
+        return Ok(None);
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+
+    entries.sort();
+
+    Ok(Some(entries))
+}
+
+/*
+ * Every file under path, or path itself, skipping backups. Symbolic links
+ * under path are skipped, they could loop or lead outside of it. Paths that
+ * can't be read are reported and counted in failures.
+ */
+fn collect_files(
+    path: &Path,
+    files: &mut Vec<PathBuf>,
+    failures: &mut usize) {
+    let entries = match dir_entries(path) {
+        Ok(Some(entries)) => { entries },
+        Ok(None) => {
+            files.push(path.to_path_buf());
+            return;
+        },
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            *failures += 1;
+            return;
+        },
+    };
+
+    for entry in entries {
+        let is_backup = entry
+            .extension()
+            .is_some_and(|extension| extension == file_redaction::BACKUP_EXTENSION);
+
+        match fs::symlink_metadata(&entry) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                eprintln!("Skipped symbolic link {}", entry.display());
+            },
+            Ok(_) if is_backup => { },
+            Ok(_) => { collect_files(&entry, files, failures); },
+            Err(e) => {
+                eprintln!("{}: {}", entry.display(), e);
+                *failures += 1;
+            },
+        }
+    }
+}
+
+fn redact(args: &[String]) -> Result<(), String> {
+    let args = parse_redact_args(args).map_err(|e| format!("{}\n\n{}", e, USAGE))?;
+
+    /* The high confidence rules */
+    let engine = ScanEngine::new(
+        ScanOptions::default()
+        .with_jwt()
+        .with_url_credentials()
+        .with_connection_strings()
+        .with_ado_legacy_pats()
+        .with_package_tokens()
+        .with_pem_private_keys());
+
+    let mut files = Vec::new();
+    let mut failures = 0;
+
+    collect_files(&args.input, &mut files, &mut failures);
+
+    /* One file failing doesn't stop the others from being redacted */
+    for file in files {
+        let report = match file_redaction::redact_file(&engine, &file, &args.options) {
+            Ok(report) => { report },
+            Err(e) => {
+                eprintln!("{}: {}", file.display(), e);
+                failures += 1;
+                continue;
+            },
+        };
+
+        if report.is_skipped() {
+            eprintln!("Skipped binary file {}", file.display());
+        } else if args.options.dry_run() {
+            print!("{}", report.diff());
+        } else if report.is_written() {
+            println!("Redacted {} secret(s) in {}", report.matches().len(), file.display());
+        }
+    }
+
+    match failures {
+        0 => { Ok(()) },
+        _ => { Err(format!("Failed to read or redact {} path(s)", failures)) },
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("redact") => { redact(&args[1..]) },
+        _ => { Err(USAGE.to_string()) },
+    };
+
+    match result {
+        Ok(()) => { ExitCode::SUCCESS },
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        },
+    }
+}
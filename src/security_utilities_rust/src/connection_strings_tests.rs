@@ -0,0 +1,155 @@
+#![cfg(test)]
+
+use crate::microsoft_security_utilities_core::connection_strings::{
+    self, COSMOS_DB_LEGACY_RULE_ID, MESSAGING_LEGACY_RULE_ID, STORAGE_ACCOUNT_LEGACY_RULE_ID,
+};
+use crate::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions, ScanState};
+use crate::microsoft_security_utilities_core::redaction;
+
+const KEY_88: &str = "dGhpcyBpcyBub3QgYSByZWFsIGtleSBidXQgaGFzIHRoZSByaWdodCBsZW5ndGggZm9yIGEgbGVnYWN5IHRlcw==";
+const KEY_44: &str = "bm90IGEgcmVhbCBrZXkgYnV0IHJpZ2h0IGxlbmd0aDM=";
+
+#[test]
+fn connection_strings_scan() {
+    assert_eq!(88, KEY_88.len());
+    assert_eq!(44, KEY_44.len());
+
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    let storage = format!("DefaultEndpointsProtocol=https;AccountName=contoso;AccountKey={};EndpointSuffix=core.windows.net", KEY_88);
+    let storage_spaced = format!("{{ \"conn\": \"StorageAccount = contoso ; storagekey = {}\" }}", KEY_88);
+    let cosmos = format!("AccountEndpoint=https://contoso.documents.azure.com:443/;AccountKey={};", KEY_88);
+    let service_bus = format!("Endpoint=sb://contoso.servicebus.windows.net/;SharedAccessKeyName=RootManageSharedAccessKey;SharedAccessKey={}", KEY_44);
+    let storage_mixed_case = format!("accountname=contoso;AccountKEy={}", KEY_88);
+    let cosmos_upper_case = format!("ACCOUNTENDPOINT=HTTPS://CONTOSO.DOCUMENTS.AZURE.COM:443/;ACCOUNTKEY={}", KEY_88);
+
+    let cases = [
+        (storage.as_str(), Some((STORAGE_ACCOUNT_LEGACY_RULE_ID, KEY_88, "AccountKey", "account", "contoso"))),
+        (storage_spaced.as_str(), Some((STORAGE_ACCOUNT_LEGACY_RULE_ID, KEY_88, "storagekey", "account", "contoso"))),
+        (cosmos.as_str(), Some((COSMOS_DB_LEGACY_RULE_ID, KEY_88, "AccountKey", "endpoint", "https://contoso.documents.azure.com:443/"))),
+        (service_bus.as_str(), Some((MESSAGING_LEGACY_RULE_ID, KEY_44, "SharedAccessKey", "endpoint", "sb://contoso.servicebus.windows.net/"))),
+        (storage_mixed_case.as_str(), Some((STORAGE_ACCOUNT_LEGACY_RULE_ID, KEY_88, "AccountKEy", "account", "contoso"))),
+        (cosmos_upper_case.as_str(), Some((COSMOS_DB_LEGACY_RULE_ID, KEY_88, "ACCOUNTKEY", "endpoint", "HTTPS://CONTOSO.DOCUMENTS.AZURE.COM:443/"))),
+    ];
+
+    for (i, (case, expected)) in cases.iter().enumerate() {
+        let matches = engine.find_matches(case.as_bytes(), true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "Case {}", i);
+
+        if let Some((id, key, field, context, value)) = expected {
+            let m = &matches[0];
+            assert_eq!(*id, m.name(), "Case {}: Id", i);
+            assert_eq!(*key, m.text(), "Case {}: Text", i);
+            assert_eq!(case.find(key).unwrap() as u64, m.start(), "Case {}: Start", i);
+            assert_eq!(Some(*field), m.context_value("field"), "Case {}: Field", i);
+            assert_eq!(Some(*value), m.context_value(context), "Case {}: Context", i);
+        }
+
+        // UTF16 LE
+        let utf16: Vec<u8> = case.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let matches = engine.find_matches(&utf16, true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "UTF16 Case {}", i);
+    }
+}
+
+#[test]
+fn connection_strings_no_context() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    let cases = [
+        // No account name before the key.
+        format!("AccountKey={};AccountName=contoso", KEY_88),
+        // Account name on another line.
+        format!("AccountName=contoso\nAccountKey={}", KEY_88),
+        // Wrong lengths and padding.
+        format!("AccountName=contoso;AccountKey={}=", KEY_88),
+        format!("AccountName=contoso;AccountKey={}", &KEY_88[1..]),
+        format!("Endpoint=sb://contoso.servicebus.windows.net/;SharedAccessKey={}=", KEY_44),
+        // Service Bus key without the endpoint.
+        format!("SharedAccessKeyName=Root;SharedAccessKey={}", KEY_44),
+        // Cosmos DB key before the host, or running into it.
+        format!("AccountKey={};AccountEndpoint=https://contoso.documents.azure.com:443/", KEY_88),
+        format!("https://contoso.documents.azure.com{}", KEY_88),
+        // Storage key field without "Key" at the end.
+        format!("AccountName=contoso;AccountKeys={}", KEY_88),
+        // Identifiable keys are left to the HIS rules.
+        format!("AccountName=contoso;AccountKey={}+ASt{}", &KEY_88[..76], &KEY_88[80..]),
+        format!("Endpoint=sb://contoso.servicebus.windows.net/;SharedAccessKey={}+ASb{}", &KEY_44[..33], &KEY_44[37..]),
+    ];
+
+    for (i, case) in cases.iter().enumerate() {
+        assert!(engine.find_matches(case.as_bytes(), true).is_empty(), "Case {}: {}", i, case);
+    }
+}
+
+#[test]
+fn connection_strings_without_field() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    let cosmos = format!("{{\"documentEndpoint\": \"https://contoso.documents.azure.com:443/\", \"primaryMasterKey\": \"{}\"}}", KEY_88);
+    let matches = engine.find_matches(cosmos.as_bytes(), true);
+    assert_eq!(1, matches.len());
+    assert_eq!(COSMOS_DB_LEGACY_RULE_ID, matches[0].name());
+    assert_eq!(KEY_88, matches[0].text());
+    assert_eq!(Some("primaryMasterKey"), matches[0].context_value("field"));
+
+    // Nothing needs to name the key, and a host mentioned twice finds it once.
+    let service_bus = format!("sb://contoso.servicebus.windows.net/ contoso.servicebus.windows.net {}", KEY_44);
+    let matches = engine.find_matches(service_bus.as_bytes(), true);
+    assert_eq!(vec![KEY_44], matches.iter().map(|m| m.text()).collect::<Vec<_>>());
+    assert_eq!(MESSAGING_LEGACY_RULE_ID, matches[0].name());
+    assert_eq!(None, matches[0].context_value("field"));
+}
+
+#[test]
+fn connection_strings_key_words() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    // Only "tKey" and "eKey", as at the end of AccountKey and StorageKey, are possible matches.
+    let data = "the monkey found the apiKey, a KEY and a hotkey; AccountKey= StorageKEY = SharedAccessKey";
+
+    let mut state = ScanState::default();
+    engine.parse_bytes(&mut state, data.as_bytes());
+
+    let anchors: Vec<_> = state
+        .possible_matches()
+        .iter()
+        .map(|check| &data[check.start() as usize..check.start() as usize + 4])
+        .collect();
+
+    assert_eq!(vec!["tkey", "tKey", "eKEY"], anchors);
+}
+
+#[test]
+fn connection_strings_with_only() {
+    let storage = format!("AccountName=contoso;AccountKey={}", KEY_88);
+    let cosmos = format!("AccountEndpoint=https://contoso.documents.azure.com:443/;AccountKey={}", KEY_88);
+    let service_bus = format!("Endpoint=sb://contoso.servicebus.windows.net/;SharedAccessKey={}", KEY_44);
+    let data = format!("{}\n{}\n{}", storage, cosmos, service_bus);
+
+    for id in [COSMOS_DB_LEGACY_RULE_ID, MESSAGING_LEGACY_RULE_ID, STORAGE_ACCOUNT_LEGACY_RULE_ID] {
+        let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings().with_only(vec![id]));
+        assert!(engine.scan_defs().iter().all(|def| def.name() == id), "{}", id);
+
+        let matches = engine.find_matches(data.as_bytes(), false);
+        assert_eq!(vec![id], matches.iter().map(|m| m.name()).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn connection_strings_parse() {
+    assert_eq!(
+        vec![("AccountName", "contoso"), ("AccountKey", "abc=="), ("EndpointSuffix", "core.windows.net")],
+        connection_strings::parse("\"conn\": \"AccountName=contoso; AccountKey = abc==;EndpointSuffix=core.windows.net\""));
+
+    assert!(connection_strings::parse("no pairs here").is_empty());
+}
+
+#[test]
+fn connection_strings_redact() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_connection_strings());
+
+    assert_eq!(
+        "AccountName=contoso;AccountKey=+++;EndpointSuffix=core.windows.net",
+        redaction::mask(&engine, &format!("AccountName=contoso;AccountKey={};EndpointSuffix=core.windows.net", KEY_88)));
+}
@@ -0,0 +1,29 @@
+#![cfg(test)]
+
+use crate::microsoft_security_utilities_core::crc32::{
+    compute_checksum, compute_checksum_castagnoli, update_checksum, CASTAGNOLI_TABLE, IEEE_TABLE,
+};
+
+/// Compare against the standard check values of both polynomials.
+#[test]
+fn crc32_check_values() {
+    assert_eq!(0xCBF43926, compute_checksum(b"123456789"));
+    assert_eq!(0xE3069283, compute_checksum_castagnoli(b"123456789"));
+
+    assert_eq!(0, compute_checksum(b""));
+    assert_eq!(0x414FA339, compute_checksum(b"The quick brown fox jumps over the lazy dog"));
+}
+
+#[test]
+fn crc32_update() {
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    for table in [&IEEE_TABLE, &CASTAGNOLI_TABLE] {
+        let expected = update_checksum(0, data, table);
+
+        for split in 0..data.len() {
+            let checksum = update_checksum(0, &data[..split], table);
+            assert_eq!(expected, update_checksum(checksum, &data[split..], table), "Split {}", split);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#![cfg(test)]
+
+use crate::microsoft_security_utilities_core::data_classification::{self, DataClass, DATA_CLASSES};
+use crate::microsoft_security_utilities_core::identifiable_scans::{ScanDefinition, ScanEngine, ScanOptions, Validation};
+
+#[test]
+fn data_classification_classify() {
+    let cases = [
+        // True positives from the .NET rules.
+        ("00000003-0000-0ff1-ce00-000000000000", Some(DataClass::GuidValue)),
+        ("aaaaaaaa-3a2b-41ca-9315-f81f3f566a95", Some(DataClass::GuidValue)),
+        ("AAAAAAAA-3a2b-41ca-9315-f81f3f566a95", Some(DataClass::GuidValue)),
+        ("127.0.0.1", Some(DataClass::IPv4)),
+        ("255.255.255.255", Some(DataClass::IPv4)),
+        ("-0001", Some(DataClass::Integer)),
+        ("0", Some(DataClass::Integer)),
+        ("1", Some(DataClass::Integer)),
+        ("0.0", Some(DataClass::Float)),
+        ("-1.0", Some(DataClass::Float)),
+        ("-.01", Some(DataClass::Float)),
+        ("-.1", Some(DataClass::Float)),
+        (".1", Some(DataClass::Float)),
+        ("1.0", Some(DataClass::Float)),
+        ("3fff:fff:ffff:ffff:ffff:ffff:ffff:ffff", Some(DataClass::IPv6)),
+        ("3FFF:FFF:FFFF:FFFF:FFFF:FFFF:FFFF:FFFF", Some(DataClass::IPv6)),
+        // False positives from the .NET rules.
+        (" 00000003-0000-0ff1-ce00-000000000000 ", None),
+        ("256.0.0.1", None),
+        ("1,000", None),
+        ("1,0", None),
+        ("3fff::", None),
+        // Not quite data.
+        ("", None),
+        ("-", None),
+        ("1.", None),
+        ("01.2.3.4", None),
+        ("1.2.3", None),
+        ("1.2.3.4.5", None),
+        ("00000003-0000-0ff1-ce00-00000000000g", None),
+        ("000000030-000-0ff1-ce00-000000000000", None),
+        ("3fff:fff:ffff:ffff:ffff:ffff:ffff:fffff", None),
+        ("notasecret", None),
+    ];
+
+    for (i, (value, expected)) in cases.iter().enumerate() {
+        assert_eq!(*expected, data_classification::classify(value), "Case {}: {}", i, value);
+    }
+}
+
+#[test]
+fn data_classification_ids() {
+    let ids: Vec<&str> = DATA_CLASSES.iter().map(DataClass::id).collect();
+    assert_eq!(vec!["DAT101/001", "DAT101/002", "DAT101/003", "DAT101/004", "DAT101/005"], ids);
+
+    assert_eq!("GuidValue", DataClass::GuidValue.name());
+    assert_eq!("an IPv4 address", DataClass::IPv4.label());
+
+    // Integers are not floats, floats need a fraction.
+    assert!(DataClass::Integer.matches("42"));
+    assert!(!DataClass::Float.matches("42"));
+}
+
+#[test]
+fn data_classification_scan_match() {
+    let def = ScanDefinition::with_context(
+        "Test",
+        b"id=",
+        b'=',
+        3,
+        64,
+        0,
+        |data, anchor| {
+            let len = data[anchor + 3..]
+                .iter()
+                .take_while(|b| !b.is_ascii_whitespace())
+                .count();
+
+            Some(Validation::new(anchor + 3, len))
+        });
+
+    let engine = ScanEngine::new(ScanOptions::empty().with_definition(def));
+    let matches = engine.find_matches(b"id=00000003-0000-0ff1-ce00-000000000000 id=notaguid", true);
+
+    assert_eq!(2, matches.len());
+    assert_eq!(Some(DataClass::GuidValue), matches[0].data_class());
+    assert_eq!(None, matches[1].data_class());
+
+    // Without the text, there is nothing to classify.
+    let matches = engine.find_matches(b"id=1234", false);
+    assert_eq!(None, matches[0].data_class());
+}
@@ -0,0 +1,227 @@
+#![cfg(test)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::microsoft_security_utilities_core::file_redaction::{self, FileEncoding, FileRedactionOptions};
+use crate::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions};
+
+const KEY: &str = "HHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHJQQJ99AEAAAAAAAAAAAAAAAAAZFU03Ml";
+
+/// A file with contents in a directory of its own, removed first if left over.
+fn test_file(
+    test: &str,
+    contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("file_redaction_tests_{}_{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("app.config");
+    fs::write(&path, contents).unwrap();
+
+    path
+}
+
+fn utf16(
+    text: &str,
+    big_endian: bool) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|u| match big_endian {
+            true => { u.to_be_bytes() },
+            false => { u.to_le_bytes() },
+        })
+        .collect()
+}
+
+#[test]
+fn file_redaction_detect_encoding() {
+    assert_eq!(FileEncoding::Utf8, FileEncoding::detect(b"key=value\n"));
+    assert_eq!(FileEncoding::Utf8, FileEncoding::detect(b""));
+    assert_eq!(FileEncoding::Utf16Le, FileEncoding::detect(&utf16("\u{FEFF}key=välue", false)));
+    assert_eq!(FileEncoding::Utf16Be, FileEncoding::detect(&utf16("\u{FEFF}key=välue", true)));
+    assert_eq!(FileEncoding::Utf16Le, FileEncoding::detect(&utf16("key=value", false)));
+    assert_eq!(FileEncoding::Utf16Be, FileEncoding::detect(&utf16("key=value", true)));
+    assert_eq!(FileEncoding::Binary, FileEncoding::detect(b"\x7FELF\x02\x01\x01\x00\x00\x00"));
+
+    // Characters past Latin-1 have NULs at the other parity now and then, like U+4E00.
+    let cjk = format!("# 設定ファイル 一\nkey={}\n", KEY);
+    assert_eq!(FileEncoding::Utf16Le, FileEncoding::detect(&utf16(&cjk, false)));
+    assert_eq!(FileEncoding::Utf16Be, FileEncoding::detect(&utf16(&cjk, true)));
+
+    // A stray NUL in text doesn't make it UTF16.
+    assert_eq!(FileEncoding::Binary, FileEncoding::detect(format!("key={}\0\n", KEY).as_bytes()));
+}
+
+#[test]
+fn file_redaction_utf8() {
+    let engine = ScanEngine::new(ScanOptions::default());
+    let contents = format!("[settings]\nname=app\nkey={0}\r\nother={0} # second\n\nlast={0}", KEY);
+    let path = test_file("utf8", contents.as_bytes());
+
+    let report = file_redaction::redact_file(&engine, &path, &FileRedactionOptions::default()).unwrap();
+
+    assert_eq!(FileEncoding::Utf8, report.encoding());
+    assert_eq!(3, report.matches().len());
+    assert!(report.is_written());
+    assert!(report.backup_path().is_none());
+    assert_eq!("[settings]\nname=app\nkey=+++\r\nother=+++ # second\n\nlast=+++", fs::read_to_string(&path).unwrap());
+    assert_eq!(
+        format!(
+            "--- {0}\n+++ {0}\n@@ -1,6 +1,6 @@\n [settings]\n name=app\n-key={1}\r\n-other={1} # second\n+key=+++\r\n+other=+++ # second\n \n-last={1}\n\\ No newline at end of file\n+last=+++\n\\ No newline at end of file\n",
+            path.display(),
+            KEY),
+        report.diff());
+
+    // Nothing left to redact, so the file is left alone.
+    let report = file_redaction::redact_file(&engine, &path, &FileRedactionOptions::default()).unwrap();
+    assert!(report.matches().is_empty());
+    assert!(!report.is_written());
+    assert!(report.diff().is_empty());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn file_redaction_dry_run_and_backup() {
+    let engine = ScanEngine::new(ScanOptions::default());
+    let contents = format!("first\nkey={}\nlast\n", KEY);
+    let path = test_file("dry_run", contents.as_bytes());
+
+    let options = FileRedactionOptions::default().with_dry_run(true);
+    let report = file_redaction::redact_file(&engine, &path, &options).unwrap();
+
+    assert!(!report.is_written());
+    assert_eq!(contents, fs::read_to_string(&path).unwrap());
+    assert_eq!(format!("--- {0}\n+++ {0}\n@@ -1,3 +1,3 @@\n first\n-key={1}\n+key=+++\n last\n", path.display(), KEY), report.diff());
+
+    let options = FileRedactionOptions::default()
+        .with_backup(true)
+        .with_template("{id}".parse().unwrap());
+
+    let report = file_redaction::redact_file(&engine, &path, &options).unwrap();
+
+    assert!(report.is_written());
+    assert_eq!(Some(file_redaction::backup_path(&path).as_path()), report.backup_path());
+    assert_eq!(contents, fs::read_to_string(report.backup_path().unwrap()).unwrap());
+    assert_eq!("first\nkey=SEC101/200\nlast\n", fs::read_to_string(&path).unwrap());
+
+    // An earlier backup is never overwritten.
+    fs::write(&path, &contents).unwrap();
+    let error = file_redaction::redact_file(&engine, &path, &options).err().unwrap();
+
+    assert_eq!(std::io::ErrorKind::AlreadyExists, error.kind());
+    assert_eq!(contents, fs::read_to_string(&path).unwrap());
+    assert_eq!(contents, fs::read_to_string(file_redaction::backup_path(&path)).unwrap());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn file_redaction_utf16() {
+    let engine = ScanEngine::new(ScanOptions::default());
+    let text = format!("\u{FEFF}name=äpp\r\nkey={}\r\n", KEY);
+
+    for big_endian in [false, true] {
+        let path = test_file(&format!("utf16_{}", big_endian), &utf16(&text, big_endian));
+        let options = FileRedactionOptions::default().with_template("[{id}]".parse().unwrap());
+
+        let report = file_redaction::redact_file(&engine, &path, &options).unwrap();
+
+        assert_eq!(1, report.matches().len(), "Big endian {}", big_endian);
+        assert!(report.matches()[0].is_utf16());
+        assert_eq!(
+            utf16("\u{FEFF}name=äpp\r\nkey=[SEC101/200]\r\n", big_endian),
+            fs::read(&path).unwrap(),
+            "Big endian {}",
+            big_endian);
+        assert_eq!(
+            format!("--- {0}\n+++ {0}\n@@ -1,2 +1,2 @@\n \u{FEFF}name=äpp\r\n-key={1}\r\n+key=[SEC101/200]\r\n", path.display(), KEY),
+            report.diff(),
+            "Big endian {}",
+            big_endian);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn file_redaction_binary() {
+    let engine = ScanEngine::new(ScanOptions::default());
+    let mut contents = b"\x00\x01\x02\x03".to_vec();
+    contents.extend_from_slice(KEY.as_bytes());
+    contents.extend_from_slice(b"\x00\xFF");
+
+    let path = test_file("binary", &contents);
+
+    let report = file_redaction::redact_file(&engine, &path, &FileRedactionOptions::default()).unwrap();
+    assert_eq!(FileEncoding::Binary, report.encoding());
+    assert!(report.is_skipped());
+    assert!(!report.is_written());
+    assert_eq!(contents, fs::read(&path).unwrap());
+
+    let options = FileRedactionOptions::default().with_force(true);
+    let report = file_redaction::redact_file(&engine, &path, &options).unwrap();
+    assert!(report.is_written());
+    assert_eq!(format!("Binary file {} differs\n", path.display()), report.diff());
+    assert_eq!(b"\x00\x01\x02\x03+++\x00\xFF".to_vec(), fs::read(&path).unwrap());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn file_redaction_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let engine = ScanEngine::new(ScanOptions::default());
+    let path = test_file("permissions", format!("key={}\n", KEY).as_bytes());
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+    let report = file_redaction::redact_file(&engine, &path, &FileRedactionOptions::default()).unwrap();
+    assert!(report.is_written());
+    assert_eq!(0o640, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+    // No temporary files are left behind.
+    assert_eq!(1, fs::read_dir(path.parent().unwrap()).unwrap().count());
+
+    // Backups are only readable by whoever could read the original.
+    fs::write(&path, format!("key={}\n", KEY)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+    let report = file_redaction::redact_file(&engine, &path, &FileRedactionOptions::default().with_backup(true)).unwrap();
+    let backup = report.backup_path().unwrap();
+    assert_eq!(0o600, fs::metadata(backup).unwrap().permissions().mode() & 0o777);
+    assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn file_redaction_follows_symlinks() {
+    use std::os::unix::fs::{symlink, MetadataExt};
+
+    let engine = ScanEngine::new(ScanOptions::default());
+    let target = test_file("symlinks", format!("key={}\n", KEY).as_bytes());
+    let link = target.with_file_name("link.config");
+    symlink(&target, &link).unwrap();
+
+    // Only root can give files away, otherwise the owner stays the same.
+    let metadata = fs::metadata(&target).unwrap();
+    let owner = match std::os::unix::fs::chown(&target, Some(1), Some(1)) {
+        Ok(()) => { (1, 1) },
+        Err(_) => { (metadata.uid(), metadata.gid()) },
+    };
+
+    let report = file_redaction::redact_file(&engine, &link, &FileRedactionOptions::default()).unwrap();
+    assert!(report.is_written());
+
+    // The link is left in place and the file it points to is redacted.
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!("key=+++\n", fs::read_to_string(&target).unwrap());
+
+    let metadata = fs::metadata(&target).unwrap();
+    assert_eq!(owner, (metadata.uid(), metadata.gid()));
+
+    fs::remove_dir_all(target.parent().unwrap()).unwrap();
+}
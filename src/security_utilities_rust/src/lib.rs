@@ -6,6 +6,28 @@ pub mod microsoft_security_utilities_core;
 mod marvin_tests;
 mod cross_company_correlating_id_tests;
 mod identifiable_scans_tests;
+mod high_performance_scanner_tests;
+mod scan_engine_handle_tests;
+mod jwt_tests;
+mod url_credentials_tests;
+mod redaction_tests;
+mod connection_strings_tests;
+mod crc32_tests;
+mod ado_pat_tests;
+mod package_tokens_tests;
+mod loose_credentials_tests;
+mod pkcs12_tests;
+mod pem_tests;
+mod unclassified_tests;
+mod data_classification_tests;
+mod literal_masker_tests;
+mod file_redaction_tests;
+mod secret_vault_tests;
+mod structured_redaction_tests;
+mod log_redaction_tests;
+mod redacted_tests;
+mod base64_rescan_tests;
+mod escape_normalization_tests;
 
 pub type IdentifiableScan = microsoft_security_utilities_core::identifiable_scans::Scan;
 pub type IdentifiableScanOptions = microsoft_security_utilities_core::identifiable_scans::ScanOptions;
@@ -0,0 +1,121 @@
+#![cfg(test)]
+
+use crate::microsoft_security_utilities_core::identifiable_scans::{ScanEngine, ScanOptions};
+use crate::microsoft_security_utilities_core::literal_masker::{self, LiteralMasker, SECRET_LITERAL_RULE_ID};
+use crate::microsoft_security_utilities_core::redaction;
+
+const SECRET: &str = "p@ss\"word/1+2";
+
+#[test]
+fn literal_masker_encoders() {
+    // Same as the .NET WellKnownLiteralEncoders.
+    assert_eq!("a\\b'c\"d\te", literal_masker::unescape_backslashes("a\\\\b\\'c\\\"d\\te"));
+    assert_eq!("p%40ss%22word%2F1%2B2", literal_masker::uri_data_escape(SECRET));
+    assert_eq!("a-b.c_d~e%20%C3%A9", literal_masker::uri_data_escape("a-b.c_d~e é"));
+
+    assert_eq!("cEBzcyJ3b3JkLzErMg==", literal_masker::base64_encode(SECRET));
+    assert_eq!("p@ss\\\"word/1+2", literal_masker::json_escape(SECRET));
+    assert_eq!("\\\\\\n\\r\\t\\b\\f\\u0001é", literal_masker::json_escape("\\\n\r\t\u{08}\u{0C}\u{01}é"));
+}
+
+#[test]
+fn literal_masker_mask() {
+    let mut masker = LiteralMasker::default();
+    masker.add_value(SECRET);
+
+    let cases = [
+        (format!("password={}", SECRET), "password=***"),
+        ("url=https://host/?p=p%40ss%22word%2F1%2B2&x=1".to_string(), "url=https://host/?p=***&x=1"),
+        ("{\"password\": \"p@ss\\\"word/1+2\"}".to_string(), "{\"password\": \"***\"}"),
+        ("Basic cEBzcyJ3b3JkLzErMg==".to_string(), "Basic ***"),
+        (format!("{}{}", SECRET, SECRET), "***"),
+        ("p@ss\"word/1+".to_string(), "p@ss\"word/1+"),
+    ];
+
+    for (i, (input, expected)) in cases.iter().enumerate() {
+        assert_eq!(*expected, masker.mask(input), "Case {}", i);
+    }
+}
+
+#[test]
+fn literal_masker_matches() {
+    let mut masker = LiteralMasker::empty();
+    masker.add_value("abcabc");
+    masker.add_value("cab");
+
+    // Overlapping occurrences are all found, ordered by start.
+    let matches = masker.find_matches(b"xabcabcabc", true);
+    let found: Vec<(u64, &str)> = matches.iter().map(|m| (m.start(), m.text())).collect();
+    assert_eq!(vec![(1, "abcabc"), (3, "cab"), (4, "abcabc"), (6, "cab")], found);
+    assert!(matches.iter().all(|m| m.name() == SECRET_LITERAL_RULE_ID));
+
+    // UTF16 LE, reporting the literal as text.
+    let utf16: Vec<u8> = "= cab".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+    let matches = masker.find_matches(&utf16, true);
+    assert_eq!(1, matches.len());
+    assert_eq!(4, matches[0].start());
+    assert_eq!(6, matches[0].len());
+    assert_eq!("cab", matches[0].text());
+
+    assert!(masker.find_matches(b"no secrets", false).is_empty());
+}
+
+#[test]
+fn literal_masker_values() {
+    let mut masker = LiteralMasker::empty();
+    masker.add_value("");
+    masker.add_value("secret");
+    masker.add_value("secret");
+    assert_eq!(&["secret".to_string()], masker.values());
+
+    // Values already added are encoded by encoders added later.
+    let masker = masker.with_encoder(|value| value.to_uppercase());
+    assert_eq!("*** ***", masker.mask("secret SECRET"));
+
+    // Encoders that return nothing are ignored.
+    let masker = masker.with_encoder(|_| String::new());
+    assert_eq!("*** ***", masker.mask("secret SECRET"));
+}
+
+#[test]
+fn literal_masker_min_len() {
+    let mut masker = LiteralMasker::empty()
+        .with_min_len(5)
+        .with_encoder(|value| format!("{}{}", value, value));
+
+    masker.add_value("abc");
+    masker.add_value("abcde");
+
+    assert_eq!(5, masker.min_len());
+    assert_eq!("abc ***", masker.mask("abc abcabc"));
+    assert_eq!("***", masker.mask("abcde"));
+
+    let masker = masker.with_min_len(0);
+    assert_eq!("***", masker.mask("abc"));
+}
+
+#[test]
+fn literal_masker_many_values() {
+    let mut masker = LiteralMasker::default();
+
+    for i in 0..5000 {
+        masker.add_value(&format!("secret-{:05}", i));
+    }
+
+    assert_eq!("a=*** b=*** c=secret-5000x", masker.mask("a=secret-00042 b=secret-04999 c=secret-5000x"));
+}
+
+#[test]
+fn literal_masker_with_scan_engine() {
+    const KEY: &str = "HHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHJQQJ99AEAAAAAAAAAAAAAAAAAZFU03Ml";
+
+    let engine = ScanEngine::new(ScanOptions::default());
+    let mut masker = LiteralMasker::default();
+    masker.add_value(SECRET);
+
+    let input = format!("key={} password={}", KEY, SECRET);
+    let mut matches = engine.find_matches(input.as_bytes(), false);
+    matches.extend(masker.find_matches(input.as_bytes(), false));
+
+    assert_eq!("key=+++ password=+++", redaction::redact(&input, &matches, "+++"));
+}
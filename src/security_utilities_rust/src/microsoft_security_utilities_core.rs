@@ -4,3 +4,29 @@
 pub mod marvin;
 pub mod cross_company_correlating_id;
 pub mod identifiable_scans;
+pub mod high_performance_scanner;
+pub mod scan_engine_handle;
+pub mod validators;
+pub mod json;
+pub mod jwt;
+pub mod url_credentials;
+pub mod connection_strings;
+pub mod redaction;
+pub mod crc32;
+pub mod ado_pat;
+pub mod package_tokens;
+pub mod loose_credentials;
+pub mod pkcs12;
+pub mod pem;
+pub mod unclassified;
+pub mod data_classification;
+pub mod literal_masker;
+pub mod file_redaction;
+pub mod structured_redaction;
+pub mod log_redaction;
+pub mod redacted;
+pub mod base64_rescan;
+pub mod escape_normalization;
+
+#[cfg(feature = "vault")]
+pub mod secret_vault;
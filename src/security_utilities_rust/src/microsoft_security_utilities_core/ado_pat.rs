@@ -0,0 +1,101 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::crc32;
+use super::identifiable_scans::ScanDefinition;
+use super::validators::CharClass;
+
+/* Same id as the .NET AdoLegacyPat rule */
+pub const ADO_LEGACY_PAT_RULE_ID: &str = "SEC101/102";
+
+pub const ADO_USER_PAT_CHECKSUM_SEED: u32 = 0xE0B9692D;
+pub const ADO_APPLICATION_PAT_CHECKSUM_SEED: u32 = 0x1019F92E;
+
+/* Legacy PATs are 52 lowercase base32 characters, which decode to 32 bytes */
+pub const ADO_LEGACY_PAT_LEN: usize = 52;
+
+const DECODED_LEN: usize = ADO_LEGACY_PAT_LEN * 5 / 8;
+
+/* The characters legacy PATs are made of, tokens are bounded by anything else */
+pub fn legacy_pat_class() -> CharClass {
+    CharClass::range(b'a', b'z').union(CharClass::range(b'2', b'7'))
+}
+
+/*
+ * Decodes base32 without padding, ignoring bits that don't fill a byte.
+ * Either case is accepted, like the .NET rule.
+ */
+fn decode_base32(input: &[u8]) -> Option<[u8; DECODED_LEN]> {
+    let mut output = [0u8; DECODED_LEN];
+    let mut bits = 0u64;
+    let mut bit_count = 0;
+    let mut pos = 0;
+
+    for b in input {
+        let value = match *b {
+            b'a'..=b'z' => { b - b'a' },
+            b'A'..=b'Z' => { b - b'A' },
+            b'2'..=b'7' => { b - b'2' + 26 },
+            _ => { return None; },
+        };
+
+        bits = bits << 5 | value as u64;
+        bit_count += 5;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            output[pos] = (bits >> bit_count) as u8;
+            pos += 1;
+        }
+    }
+
+    Some(output)
+}
+
+/*
+ * Checks the checksum embedded in a legacy PAT, which is the CRC32 of the
+ * other 28 decoded bytes XORed with the user or application seed.
+ */
+pub fn is_checksum_valid(token: &[u8]) -> bool {
+    if token.len() != ADO_LEGACY_PAT_LEN {
+        return false;
+    }
+
+    let bytes = match decode_base32(token) {
+        Some(bytes) => { bytes },
+        None => { return false; },
+    };
+
+    /* The checksum is spread over these bytes, little endian */
+    let embedded = u32::from_le_bytes([bytes[22], bytes[8], bytes[7], bytes[2]]);
+
+    let mut token_bytes = [0u8; 28];
+    token_bytes[0..2].copy_from_slice(&bytes[0..2]);
+    token_bytes[2..6].copy_from_slice(&bytes[3..7]);
+    token_bytes[6..19].copy_from_slice(&bytes[9..22]);
+    token_bytes[19..28].copy_from_slice(&bytes[23..32]);
+
+    let crc = crc32::compute_checksum(&token_bytes);
+
+    embedded == crc ^ ADO_USER_PAT_CHECKSUM_SEED ||
+    embedded == crc ^ ADO_APPLICATION_PAT_CHECKSUM_SEED
+}
+
+/*
+ * Legacy PATs have no signature, so this definition is checked on every
+ * byte. Only tokens with a valid checksum match.
+ */
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    vec![
+        ScanDefinition::unanchored(
+            ADO_LEGACY_PAT_RULE_ID,
+            legacy_pat_class(),
+            ADO_LEGACY_PAT_LEN,
+            |data| {
+                match is_checksum_valid(data) {
+                    true => { data.len() },
+                    false => { 0 },
+                }
+            }),
+    ]
+}
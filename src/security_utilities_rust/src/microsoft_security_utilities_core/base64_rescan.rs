@@ -0,0 +1,263 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fmt;
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine as _;
+
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+
+/* Shorter runs are left alone, base64 of 15 bytes is 20 characters */
+pub const DEFAULT_MIN_RUN_LEN: usize = 20;
+
+/* Base64 inside base64 is found, a third level isn't looked for */
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+
+/* Padding is optional and the last character may have stray bits */
+const LENIENT: GeneralPurposeConfig = GeneralPurposeConfig::new()
+    .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+    .with_decode_allow_trailing_bits(true);
+
+const STANDARD_LENIENT: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, LENIENT);
+const URL_SAFE_LENIENT: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, LENIENT);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Base64,
+    /* RFC 4648 base64url, '-' and '_' instead of '+' and '/' */
+    Base64Url,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Base64 => { "base64" },
+            Self::Base64Url => { "base64url" },
+        }
+    }
+}
+
+/* A run of encoded data that was decoded, at start in what contained it */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodingStep {
+    encoding: Encoding,
+    start: usize,
+    len: usize,
+}
+
+impl DecodingStep {
+    pub fn encoding(&self) -> Encoding { self.encoding }
+
+    pub fn start(&self) -> usize { self.start }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+}
+
+impl fmt::Display for DecodingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.encoding.name(), self.start)
+    }
+}
+
+/* A match in decoded data, and how the data was decoded from the input */
+pub struct DecodedMatch {
+    chain: Vec<DecodingStep>,
+    /* The innermost decoded data is UTF8 */
+    utf8: bool,
+    scan_match: ScanMatch,
+}
+
+impl DecodedMatch {
+    /* Start of the outermost encoded run in the input, redact from here */
+    pub fn outer_start(&self) -> usize { self.chain[0].start }
+
+    pub fn outer_len(&self) -> usize { self.chain[0].len }
+
+    /* The runs decoded, outermost first, each in the data decoded before it */
+    pub fn chain(&self) -> &[DecodingStep] { &self.chain }
+
+    /* The match, its start is in the innermost decoded data */
+    pub fn scan_match(&self) -> &ScanMatch { &self.scan_match }
+}
+
+impl fmt::Display for DecodedMatch {
+    /* Such as "base64 at offset 12 → utf8 at offset 5" */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.chain {
+            write!(f, "{} → ", step)?;
+        }
+
+        let data = match (self.scan_match.is_utf16(), self.utf8) {
+            (true, _) => { "utf16" },
+            (false, true) => { "utf8" },
+            (false, false) => { "binary" },
+        };
+
+        write!(f, "{} at offset {}", data, self.scan_match.start())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RescanOptions {
+    min_run_len: usize,
+    max_depth: usize,
+}
+
+impl RescanOptions {
+    /* Runs shorter than this, padding excluded, aren't decoded */
+    pub fn with_min_run_len(
+        self,
+        min_run_len: usize) -> Self {
+        let mut clone = self;
+
+        clone.min_run_len = min_run_len;
+
+        clone
+    }
+
+    /* How many encodings deep to decode, 0 decodes nothing */
+    pub fn with_max_depth(
+        self,
+        max_depth: usize) -> Self {
+        let mut clone = self;
+
+        clone.max_depth = max_depth;
+
+        clone
+    }
+
+    pub fn min_run_len(&self) -> usize { self.min_run_len }
+
+    pub fn max_depth(&self) -> usize { self.max_depth }
+}
+
+impl Default for RescanOptions {
+    fn default() -> Self {
+        Self {
+            min_run_len: DEFAULT_MIN_RUN_LEN,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'-' | b'_')
+}
+
+/*
+ * Maximal runs of base64 or base64url characters, with their padding,
+ * that are at least min_len long. Runs mixing both alphabets aren't
+ * either and are skipped.
+ */
+fn encoded_runs(
+    data: &[u8],
+    min_len: usize) -> Vec<DecodingStep> {
+    let mut runs = Vec::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        if !is_base64_char(data[i]) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+
+        while i < data.len() && is_base64_char(data[i]) {
+            i += 1;
+        }
+
+        let run = &data[start..i];
+        let standard = run.iter().any(|b| matches!(b, b'+' | b'/'));
+        let url_safe = run.iter().any(|b| matches!(b, b'-' | b'_'));
+
+        let mut end = i;
+
+        while end < data.len() && end - i < 2 && data[end] == b'=' {
+            end += 1;
+        }
+
+        /* A single character past a multiple of four can't be decoded */
+        if run.len() < min_len || run.len() % 4 == 1 || (standard && url_safe) {
+            continue;
+        }
+
+        runs.push(DecodingStep {
+            encoding: match url_safe {
+                true => { Encoding::Base64Url },
+                false => { Encoding::Base64 },
+            },
+            start,
+            len: end - start,
+        });
+
+        i = end;
+    }
+
+    runs
+}
+
+fn rescan(
+    engine: &ScanEngine,
+    data: &[u8],
+    want_text: bool,
+    options: &RescanOptions,
+    chain: &mut Vec<DecodingStep>,
+    matches: &mut Vec<DecodedMatch>) {
+    if chain.len() >= options.max_depth {
+        return;
+    }
+
+    for run in encoded_runs(data, options.min_run_len) {
+        let encoded = &data[run.start..run.start + run.len];
+
+        let decoded = match run.encoding {
+            Encoding::Base64 => { STANDARD_LENIENT.decode(encoded) },
+            Encoding::Base64Url => { URL_SAFE_LENIENT.decode(encoded) },
+        };
+
+        let decoded = match decoded {
+            Ok(decoded) if !decoded.is_empty() => { decoded },
+            _ => { continue; },
+        };
+
+        chain.push(run);
+
+        let utf8 = std::str::from_utf8(&decoded).is_ok();
+
+        matches.extend(
+            engine
+                .find_matches(&decoded, want_text)
+                .into_iter()
+                .map(|scan_match| DecodedMatch {
+                    chain: chain.clone(),
+                    utf8,
+                    scan_match,
+                }));
+
+        rescan(engine, &decoded, want_text, options, chain, matches);
+        chain.pop();
+    }
+}
+
+/*
+ * Finds what engine matches in base64 and base64url runs of data once
+ * decoded, recursively up to the depth in options. Only matches in
+ * decoded data are returned, ordered by outer start, use
+ * ScanEngine::find_matches() for the rest.
+ */
+pub fn find_matches(
+    engine: &ScanEngine,
+    data: &[u8],
+    want_text: bool,
+    options: &RescanOptions) -> Vec<DecodedMatch> {
+    let mut matches = Vec::new();
+
+    rescan(engine, data, want_text, options, &mut Vec::new(), &mut matches);
+
+    matches
+}
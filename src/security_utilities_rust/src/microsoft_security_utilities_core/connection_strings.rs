@@ -0,0 +1,338 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::identifiable_scans::{case_variants, ScanDefinition, Validation};
+
+pub const COSMOS_DB_LEGACY_RULE_ID: &str = "SEC101/104";
+pub const MESSAGING_LEGACY_RULE_ID: &str = "SEC101/105";
+pub const STORAGE_ACCOUNT_LEGACY_RULE_ID: &str = "SEC101/106";
+
+/*
+ * Legacy Azure keys have no signature of their own, they are only found by
+ * what is around them. These rules mirror the .NET ones:
+ *
+ * SEC101/104 .documents.azure.com before [0-9a-z/+]{86}==
+ * SEC101/105 .servicebus.windows before [0-9a-z/+]{43}=
+ * SEC101/106 (AccountName|StorageName|StorageAccount)= before (Account|Storage)Key=[0-9a-z\/+]{86}==
+ *
+ * all case insensitive and on the same line. Rather than search every line,
+ * the engine anchors on the Cosmos DB and Service Bus hosts and looks ahead
+ * for the key, and on the "tKey" or "eKey" that ends the name of the storage
+ * key field, AccountKey or StorageKey, and looks back for the account. Just
+ * "Key" would anchor on every monkey and apiKey. Signatures are case
+ * sensitive, so there is one for every casing of them.
+ */
+const STORAGE_SIGNATURES: [&[u8]; 2] = [b"tkey", b"ekey"];
+
+/* The "k" of the storage signatures, rarer than the letters around it */
+const STORAGE_SIG_CHAR_INDEX: usize = 1;
+
+const STORAGE_SIGNATURE_LEN: usize = 4;
+const COSMOS_DB_HOST: &[u8] = b".documents.azure.com";
+const MESSAGING_HOST: &[u8] = b".servicebus.windows";
+
+/*
+ * The hosts are anchored on the "s.az" of documents.azure and the "us.w" of
+ * servicebus.windows, at these offsets in the host, and on the "z" and "w"
+ * in them. Rare letters let the scan skip most text, where the dot before
+ * them is in every other line of code.
+ */
+const HOST_SIGNATURE_LEN: usize = 4;
+const COSMOS_DB_SIGNATURE: (usize, usize) = (9, 3);
+const MESSAGING_SIGNATURE: (usize, usize) = (9, 3);
+
+/* How far apart on the line the account or host and the key can be */
+const CONNECTION_STRING_LOOKBEHIND: usize = 1024;
+
+/* "tKey", spacing around "=" and the longest value with its padding */
+const CONNECTION_STRING_MAX_LEN: usize = STORAGE_SIGNATURE_LEN + 64 + 88 + 1;
+
+/* The host, what can be between it and the key, the key and the byte after */
+const HOST_MAX_LEN: usize = COSMOS_DB_HOST.len() + CONNECTION_STRING_LOOKBEHIND + 88 + 1;
+
+/* Identifiable keys have a signature here and are left to the HIS rules */
+const IDENTIFIABLE_88_SIGNATURE_OFFSET: usize = 76;
+const IDENTIFIABLE_44_SIGNATURE_OFFSET: usize = 33;
+
+const ACCOUNT_FIELDS: [&str; 3] = ["AccountName", "StorageName", "StorageAccount"];
+const ENDPOINT_FIELDS: [&str; 2] = ["AccountEndpoint", "Endpoint"];
+
+/*
+ * Splits a connection string into its key and value pairs, in order. Keys
+ * are the last word before the "=", so a connection string quoted in JSON
+ * or code still yields its own keys, and values stop at quotes or spaces.
+ */
+pub fn parse(connection_string: &str) -> Vec<(&str, &str)> {
+    connection_string
+        .split(';')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+
+            let key = key.trim_end();
+            let key = key.rsplit(|c: char| !c.is_ascii_alphanumeric()).next()?;
+
+            let value = value.trim_start();
+            let value = value.split(|c: char| c == '"' || c == '\'' || c.is_whitespace()).next()?;
+
+            match key.is_empty() {
+                true => { None },
+                false => { Some((key, value)) },
+            }
+        })
+        .collect()
+}
+
+fn is_key_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'/' || b == b'+'
+}
+
+fn contains_ignore_case(
+    haystack: &[u8],
+    needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|w| w.eq_ignore_ascii_case(needle))
+}
+
+/* Index of the first "name\s*=" in line, ignoring case */
+fn find_field(
+    line: &[u8],
+    name: &str) -> Option<usize> {
+    let name = name.as_bytes();
+
+    (0..line.len()).find(|i| {
+        let rest = &line[*i..];
+
+        if rest.len() < name.len() || !rest[..name.len()].eq_ignore_ascii_case(name) {
+            return false;
+        }
+
+        rest[name.len()..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            == Some(&b'=')
+    })
+}
+
+fn field_value<'a>(
+    pairs: &[(&'a str, &'a str)],
+    names: &[&str]) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(key, _)| names.iter().any(|name| key.eq_ignore_ascii_case(name)))
+        .map(|(_, value)| *value)
+}
+
+/* Where the line around offset starts and ends */
+fn line_bounds(
+    data: &[u8],
+    offset: usize) -> (usize, usize) {
+    let is_newline = |b: &u8| *b == b'\n' || *b == b'\r';
+
+    let start = data[..offset]
+        .iter()
+        .rposition(is_newline)
+        .map_or(0, |i| i + 1);
+
+    let end = data[offset..]
+        .iter()
+        .position(is_newline)
+        .map_or(data.len(), |i| offset + i);
+
+    (start, end)
+}
+
+/* The name of the field a value is assigned to, as in Key=value or "Key": "value" */
+fn field_before(
+    data: &[u8],
+    value_start: usize) -> Option<&str> {
+    let mut end = value_start;
+    let mut separator = false;
+
+    while end > 0 {
+        match data[end - 1] {
+            b' ' | b'\t' | b'"' | b'\'' => { end -= 1; },
+            b'=' | b':' if !separator => {
+                separator = true;
+                end -= 1;
+            },
+            _ => { break; },
+        }
+    }
+
+    let start = data[..end]
+        .iter()
+        .rposition(|b| !b.is_ascii_alphanumeric())
+        .map_or(0, |i| i + 1);
+
+    match separator && start < end {
+        true => { std::str::from_utf8(&data[start..end]).ok() },
+        false => { None },
+    }
+}
+
+fn validate_storage(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    /* The .NET patterns don't span lines */
+    let (line_start, line_end) = line_bounds(data, anchor);
+
+    let field_start = data[line_start..anchor]
+        .iter()
+        .rposition(|b| !b.is_ascii_alphabetic())
+        .map_or(line_start, |i| line_start + i + 1);
+
+    let field_end = anchor + STORAGE_SIGNATURE_LEN;
+    let field = &data[field_start..field_end];
+
+    let storage_field = [b"AccountKey", b"StorageKey"]
+        .iter()
+        .any(|name| field.len() >= name.len() && field[field.len() - name.len()..].eq_ignore_ascii_case(*name));
+
+    if !storage_field || !ACCOUNT_FIELDS.iter().any(|name| find_field(&data[line_start..field_start], name).is_some()) {
+        return None;
+    }
+
+    let mut pos = field_end;
+
+    while data.get(pos).is_some_and(|b| *b == b' ' || *b == b'\t') {
+        pos += 1;
+    }
+
+    if data.get(pos) != Some(&b'=') {
+        return None;
+    }
+
+    pos += 1;
+
+    while data.get(pos).is_some_and(|b| *b == b' ' || *b == b'\t') {
+        pos += 1;
+    }
+
+    let value_start = pos;
+    let key_len = data[value_start..].iter().take_while(|b| is_key_char(**b)).count();
+    let padding = data[value_start + key_len..].iter().take_while(|b| **b == b'=').count();
+
+    if (key_len, padding) != (86, 2) ||
+       &data[value_start + IDENTIFIABLE_88_SIGNATURE_OFFSET..value_start + IDENTIFIABLE_88_SIGNATURE_OFFSET + 4] == b"+ASt" {
+        return None;
+    }
+
+    let line = String::from_utf8_lossy(&data[line_start..line_end]);
+    let pairs = parse(&line);
+
+    let validation = Validation::new(value_start, key_len + padding)
+        .with_context("field", std::str::from_utf8(field).ok()?);
+
+    match field_value(&pairs, &ACCOUNT_FIELDS) {
+        Some(account) => { Some(validation.with_context("account", account)) },
+        None => { Some(validation) },
+    }
+}
+
+/*
+ * Finds the first key of key_len characters and padding after the host at
+ * anchor on the same line, any field or none before it. A key after the
+ * host is mentioned again is left to that mention, so it's found once.
+ */
+fn validate_host(
+    data: &[u8],
+    anchor: usize,
+    host: &[u8],
+    key_len: usize,
+    padding: usize,
+    identifiable_signature: (usize, &[u8])) -> Option<Validation> {
+    let host_end = anchor + host.len();
+
+    if !data.get(anchor..host_end)?.eq_ignore_ascii_case(host) {
+        return None;
+    }
+
+    let (line_start, line_end) = line_bounds(data, anchor);
+    let mut pos = host_end;
+
+    let value_start = loop {
+        if pos >= line_end || pos > host_end + CONNECTION_STRING_LOOKBEHIND {
+            return None;
+        }
+
+        let start = pos;
+        let len = data[start..line_end].iter().take_while(|b| is_key_char(**b)).count();
+        let equals = data[start + len..line_end].iter().take_while(|b| **b == b'=').count();
+
+        /* The host ends in a key character, so a key can't start right after it */
+        if start > host_end && (len, equals) == (key_len, padding) {
+            break start;
+        }
+
+        /* Keys start after a character that can't be in one */
+        pos = start + len + equals.max(1);
+    };
+
+    let (offset, signature) = identifiable_signature;
+
+    if &data[value_start + offset..value_start + offset + signature.len()] == signature ||
+       contains_ignore_case(&data[host_end..value_start], host) {
+        return None;
+    }
+
+    let line = String::from_utf8_lossy(&data[line_start..line_end]);
+    let pairs = parse(&line);
+
+    let mut validation = Validation::new(value_start, key_len + padding);
+
+    if let Some(field) = field_before(&data[line_start..], value_start - line_start) {
+        validation = validation.with_context("field", field);
+    }
+
+    match field_value(&pairs, &ENDPOINT_FIELDS) {
+        Some(endpoint) => { Some(validation.with_context("endpoint", endpoint)) },
+        None => { Some(validation) },
+    }
+}
+
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    let mut defs = Vec::new();
+
+    let (offset, sig_char_index) = COSMOS_DB_SIGNATURE;
+
+    for sig in case_variants(&COSMOS_DB_HOST[offset..offset + HOST_SIGNATURE_LEN]) {
+        defs.push(
+            ScanDefinition::with_context(
+                COSMOS_DB_LEGACY_RULE_ID,
+                &sig,
+                sig[sig_char_index],
+                (offset + HOST_SIGNATURE_LEN) as u64,
+                HOST_MAX_LEN,
+                CONNECTION_STRING_LOOKBEHIND,
+                |data, anchor| validate_host(data, anchor, COSMOS_DB_HOST, 86, 2, (IDENTIFIABLE_88_SIGNATURE_OFFSET, b"ACDb"))));
+    }
+
+    let (offset, sig_char_index) = MESSAGING_SIGNATURE;
+
+    for sig in case_variants(&MESSAGING_HOST[offset..offset + HOST_SIGNATURE_LEN]) {
+        defs.push(
+            ScanDefinition::with_context(
+                MESSAGING_LEGACY_RULE_ID,
+                &sig,
+                sig[sig_char_index],
+                (offset + HOST_SIGNATURE_LEN) as u64,
+                HOST_MAX_LEN,
+                CONNECTION_STRING_LOOKBEHIND,
+                |data, anchor| validate_host(data, anchor, MESSAGING_HOST, 43, 1, (IDENTIFIABLE_44_SIGNATURE_OFFSET, b"+ASb"))));
+    }
+
+    for sig in STORAGE_SIGNATURES.iter().flat_map(|sig| case_variants(sig)) {
+        defs.push(
+            ScanDefinition::with_context(
+                STORAGE_ACCOUNT_LEGACY_RULE_ID,
+                &sig,
+                sig[STORAGE_SIG_CHAR_INDEX],
+                STORAGE_SIGNATURE_LEN as u64,
+                CONNECTION_STRING_MAX_LEN,
+                CONNECTION_STRING_LOOKBEHIND,
+                validate_storage));
+    }
+
+    defs
+}
@@ -0,0 +1,66 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+//! This is a Rust implementation of CRC32, equivalent to the C# `Crc32` class used to validate
+//! checksums embedded in keys such as Azure DevOps legacy personal access tokens.
+
+/// The 'reversed representation' of the CRC-32-IEEE 802.3 polynomial 0x04C11DB7,
+/// for little-endian implementations.
+pub const IEEE_POLYNOMIAL: u32 = 0xEDB88320;
+
+/// Castagnoli's polynomial, used in iSCSI. Has better error detection
+/// characteristics than IEEE, see https://dx.doi.org/10.1109/26.231911.
+pub const CASTAGNOLI_POLYNOMIAL: u32 = 0x82F63B78;
+
+/// Lookup table for the IEEE polynomial, the default.
+pub static IEEE_TABLE: [u32; 256] = create_table(IEEE_POLYNOMIAL);
+
+/// Lookup table for the Castagnoli polynomial.
+pub static CASTAGNOLI_TABLE: [u32; 256] = create_table(CASTAGNOLI_POLYNOMIAL);
+
+/// Creates the lookup table for a reversed polynomial.
+pub const fn create_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut value = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = match value & 1 {
+                1 => (value >> 1) ^ polynomial,
+                _ => value >> 1,
+            };
+
+            bit += 1;
+        }
+
+        table[i] = value;
+        i += 1;
+    }
+
+    table
+}
+
+/// Computes the CRC32 of a slice with the IEEE polynomial.
+pub fn compute_checksum(data: &[u8]) -> u32 {
+    update_checksum(0, data, &IEEE_TABLE)
+}
+
+/// Computes the CRC32 of a slice with the Castagnoli polynomial (CRC32C).
+pub fn compute_checksum_castagnoli(data: &[u8]) -> u32 {
+    update_checksum(0, data, &CASTAGNOLI_TABLE)
+}
+
+/// Continues a checksum returned by a previous call with more data, so that
+/// data can be checksummed in pieces. Start with a checksum of 0.
+pub fn update_checksum(checksum: u32, data: &[u8], table: &[u32; 256]) -> u32 {
+    let mut crc = !checksum;
+
+    for b in data {
+        crc = table[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+
+    !crc
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+pub const GUID_VALUE_RULE_ID: &str = "DAT101/001";
+pub const IPV4_RULE_ID: &str = "DAT101/002";
+pub const INTEGER_RULE_ID: &str = "DAT101/003";
+pub const FLOAT_RULE_ID: &str = "DAT101/004";
+pub const IPV6_RULE_ID: &str = "DAT101/005";
+
+/*
+ * Ports of the .NET DataClassification rules, which tell that a value is
+ * data rather than a secret. Like .NET, the whole value must match, and
+ * only ASCII digits are digits.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataClass {
+    /* (?i)^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$ */
+    GuidValue,
+    /* Dotted decimal, without leading zeros */
+    IPv4,
+    /* ^-?\d+$ */
+    Integer,
+    /* ^-?(\d+)?\.\d+$ */
+    Float,
+    /* (?i)^([0-9a-f]{1,4}:){7}[0-9a-f]{1,4}$, without zero compression */
+    IPv6,
+}
+
+impl DataClass {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::GuidValue => { GUID_VALUE_RULE_ID },
+            Self::IPv4 => { IPV4_RULE_ID },
+            Self::Integer => { INTEGER_RULE_ID },
+            Self::Float => { FLOAT_RULE_ID },
+            Self::IPv6 => { IPV6_RULE_ID },
+        }
+    }
+
+    /* Same as the .NET rule names */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GuidValue => { "GuidValue" },
+            Self::IPv4 => { "IPv4" },
+            Self::Integer => { "Integer" },
+            Self::Float => { "Float" },
+            Self::IPv6 => { "IPv6" },
+        }
+    }
+
+    /* Describes the data, such as "a GUID" */
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::GuidValue => { "a GUID" },
+            Self::IPv4 => { "an IPv4 address" },
+            Self::Integer => { "an integer" },
+            Self::Float => { "a floating point number" },
+            Self::IPv6 => { "an IPv6 address" },
+        }
+    }
+
+    /* Whether all of value is this kind of data */
+    pub fn matches(
+        &self,
+        value: &str) -> bool {
+        let value = value.as_bytes();
+
+        match self {
+            Self::GuidValue => { is_guid(value) },
+            Self::IPv4 => { is_ipv4(value) },
+            Self::Integer => { is_integer(value) },
+            Self::Float => { is_float(value) },
+            Self::IPv6 => { is_ipv6(value) },
+        }
+    }
+}
+
+/* In the same order as .NET */
+pub const DATA_CLASSES: [DataClass; 5] = [
+    DataClass::GuidValue,
+    DataClass::IPv4,
+    DataClass::Integer,
+    DataClass::Float,
+    DataClass::IPv6,
+];
+
+/* The first class all of value matches, None when it could be a secret */
+pub fn classify(value: &str) -> Option<DataClass> {
+    DATA_CLASSES
+        .into_iter()
+        .find(|class| class.matches(value))
+}
+
+fn is_digits(value: &[u8]) -> bool {
+    !value.is_empty() && value.iter().all(u8::is_ascii_digit)
+}
+
+fn is_hex_groups(
+    value: &[u8],
+    separator: u8,
+    lens: &[(usize, usize)]) -> bool {
+    let groups: Vec<&[u8]> = value.split(|b| *b == separator).collect();
+
+    groups.len() == lens.len() &&
+    groups
+        .iter()
+        .zip(lens)
+        .all(|(group, (min, max))| {
+            (*min..=*max).contains(&group.len()) && group.iter().all(u8::is_ascii_hexdigit)
+        })
+}
+
+fn is_guid(value: &[u8]) -> bool {
+    is_hex_groups(value, b'-', &[(8, 8), (4, 4), (4, 4), (4, 4), (12, 12)])
+}
+
+fn is_ipv6(value: &[u8]) -> bool {
+    is_hex_groups(value, b':', &[(1, 4); 8])
+}
+
+fn is_ipv4(value: &[u8]) -> bool {
+    let octets: Vec<&[u8]> = value.split(|b| *b == b'.').collect();
+
+    octets.len() == 4 &&
+    octets.iter().all(|octet| {
+        is_digits(octet) &&
+        octet.len() <= 3 &&
+        (octet.len() == 1 || octet[0] != b'0') &&
+        std::str::from_utf8(octet).ok().and_then(|octet| octet.parse::<u8>().ok()).is_some()
+    })
+}
+
+fn is_integer(value: &[u8]) -> bool {
+    is_digits(value.strip_prefix(b"-").unwrap_or(value))
+}
+
+fn is_float(value: &[u8]) -> bool {
+    let value = value.strip_prefix(b"-").unwrap_or(value);
+
+    match value.iter().position(|b| *b == b'.') {
+        Some(dot) => {
+            value[..dot].iter().all(u8::is_ascii_digit) && is_digits(&value[dot + 1..])
+        },
+        None => { false },
+    }
+}
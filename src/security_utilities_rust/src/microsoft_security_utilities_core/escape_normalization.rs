@@ -0,0 +1,271 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+
+/* An escape sequence and what it was replaced with in normalized data */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Escape {
+    start: usize,
+    end: usize,
+    original_start: usize,
+    original_end: usize,
+}
+
+/*
+ * A copy of data with JSON, C and percent escapes replaced by what they
+ * escape, and where each escape was in data so offsets can be mapped back.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NormalizedData {
+    data: Vec<u8>,
+    /* Ordered, and only the escapes, so unescaped data costs nothing */
+    escapes: Vec<Escape>,
+}
+
+impl NormalizedData {
+    pub fn data(&self) -> &[u8] { &self.data }
+
+    /* Nothing was unescaped, data is the original */
+    pub fn is_unchanged(&self) -> bool { self.escapes.is_empty() }
+
+    /* The escape offset is in, if it's in one */
+    fn escape_at(
+        &self,
+        offset: usize) -> Option<&Escape> {
+        match self.escapes.partition_point(|escape| escape.start <= offset) {
+            0 => { None },
+            index => { Some(&self.escapes[index - 1]) },
+        }
+    }
+
+    /*
+     * Offset in the original data of a range starting at offset in the
+     * normalized data. A range starting inside what an escape was replaced
+     * with starts at the escape.
+     */
+    pub fn original_start(
+        &self,
+        offset: usize) -> usize {
+        match self.escape_at(offset) {
+            None => { offset },
+            Some(escape) if offset < escape.end => { escape.original_start },
+            Some(escape) => { escape.original_end + (offset - escape.end) },
+        }
+    }
+
+    /*
+     * Offset in the original data of a range ending at end in the
+     * normalized data. A range ending inside what an escape was replaced
+     * with ends after the escape.
+     */
+    pub fn original_end(
+        &self,
+        end: usize) -> usize {
+        if end == 0 {
+            return 0;
+        }
+
+        match self.escape_at(end - 1) {
+            None => { end },
+            Some(escape) if end - 1 < escape.end => { escape.original_end },
+            Some(escape) => { escape.original_end + (end - escape.end) },
+        }
+    }
+}
+
+fn hex_value(b: u8) -> Option<u32> {
+    (b as char).to_digit(16)
+}
+
+/* The value of the count hex digits at start, when there are that many */
+fn hex_digits(
+    data: &[u8],
+    start: usize,
+    count: usize) -> Option<u32> {
+    data.get(start..start + count)?
+        .iter()
+        .try_fold(0, |value, &b| Some(value * 16 + hex_value(b)?))
+}
+
+/* Up to 4 bytes an escape is replaced with, and how many there are */
+type Unescaped = ([u8; 4], usize);
+
+/* Only ASCII, so UTF8 stays UTF8 once unescaped */
+fn byte(b: u32) -> Option<Unescaped> {
+    match b {
+        0..=0x7F => { Some(([b as u8, 0, 0, 0], 1)) },
+        _ => { None },
+    }
+}
+
+fn utf8(c: char) -> Unescaped {
+    let mut bytes = [0; 4];
+    let len = c.encode_utf8(&mut bytes).len();
+
+    (bytes, len)
+}
+
+/* \uXXXX, with the low surrogate that follows a high one */
+fn unicode_escape(
+    data: &[u8],
+    i: usize) -> Option<(Unescaped, usize)> {
+    let high = hex_digits(data, i + 2, 4)?;
+
+    if !(0xD800..0xDC00).contains(&high) {
+        return Some((utf8(char::from_u32(high)?), 6));
+    }
+
+    if data.get(i + 6..i + 8) != Some(b"\\u") {
+        return None;
+    }
+
+    let low = hex_digits(data, i + 8, 4).filter(|low| (0xDC00..0xE000).contains(low))?;
+    let c = char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))?;
+
+    Some((utf8(c), 12))
+}
+
+/* \ooo, up to three octal digits that fit in a byte */
+fn octal_escape(
+    data: &[u8],
+    i: usize) -> Option<(Unescaped, usize)> {
+    let mut value = 0;
+    let mut len = 1;
+
+    while len <= 3 {
+        match data.get(i + len) {
+            Some(&b @ b'0'..=b'7') if value * 8 + ((b - b'0') as u32) <= 0xFF => {
+                value = value * 8 + (b - b'0') as u32;
+                len += 1;
+            },
+            _ => { break; },
+        }
+    }
+
+    Some((byte(value)?, len))
+}
+
+/*
+ * What the escape at i is replaced with and how long it is, None when
+ * there's no valid escape there.
+ */
+fn unescape(
+    data: &[u8],
+    i: usize) -> Option<(Unescaped, usize)> {
+    match data[i] {
+        b'%' => { Some((byte(hex_digits(data, i + 1, 2)?)?, 3)) },
+        b'\\' => {
+            let unescaped = match *data.get(i + 1)? {
+                b @ (b'"' | b'\'' | b'\\' | b'/' | b'?') => { b },
+                b'a' => { 0x07 },
+                b'b' => { 0x08 },
+                b'f' => { 0x0C },
+                b'n' => { b'\n' },
+                b'r' => { b'\r' },
+                b't' => { b'\t' },
+                b'v' => { 0x0B },
+                b'x' => { return Some((byte(hex_digits(data, i + 2, 2)?)?, 4)); },
+                b'u' => { return unicode_escape(data, i); },
+                b'U' => { return Some((utf8(char::from_u32(hex_digits(data, i + 2, 8)?)?), 10)); },
+                b'0'..=b'7' => { return octal_escape(data, i); },
+                _ => { return None; },
+            };
+
+            Some((byte(unescaped as u32)?, 2))
+        },
+        _ => { None },
+    }
+}
+
+/*
+ * data with escapes replaced in a single pass, so an escaped backslash or
+ * percent sign doesn't start another escape. Recognized are the JSON
+ * and C backslash escapes, \xHH, \uXXXX with surrogate pairs, \UXXXXXXXX,
+ * octal escapes and URL percent encoding. Invalid escapes, and those of
+ * single bytes that aren't ASCII, are left as they are, and '+' isn't
+ * turned into a space.
+ */
+pub fn normalize(data: &[u8]) -> NormalizedData {
+    let mut normalized = NormalizedData {
+        data: Vec::new(),
+        escapes: Vec::new(),
+    };
+
+    if !data.iter().any(|b| matches!(b, b'\\' | b'%')) {
+        normalized.data = data.to_vec();
+        return normalized;
+    }
+
+    normalized.data.reserve(data.len());
+
+    let mut i = 0;
+
+    while i < data.len() {
+        match unescape(data, i) {
+            Some(((bytes, count), len)) => {
+                let start = normalized.data.len();
+
+                normalized.data.extend_from_slice(&bytes[..count]);
+                normalized.escapes.push(Escape {
+                    start,
+                    end: normalized.data.len(),
+                    original_start: i,
+                    original_end: i + len,
+                });
+
+                i += len;
+            },
+            None => {
+                normalized.data.push(data[i]);
+                i += 1;
+            },
+        }
+    }
+
+    normalized
+}
+
+/*
+ * Same as ScanEngine::find_matches(), also finding secrets that are only
+ * whole once data is unescaped, see normalize(). Those are mapped back to
+ * the escaped range in data, so they're reported and redacted there, and
+ * their text is unescaped. Ordered by start.
+ */
+pub fn find_matches(
+    engine: &ScanEngine,
+    data: &[u8],
+    want_text: bool) -> Vec<ScanMatch> {
+    let mut matches = engine.find_matches(data, want_text);
+    let normalized = normalize(data);
+
+    if normalized.is_unchanged() {
+        return matches;
+    }
+
+    for scan_match in engine.find_matches(normalized.data(), want_text) {
+        let scan_match = scan_match.remapped(
+            |start| normalized.original_start(start as usize) as u64,
+            |end| normalized.original_end(end as usize) as u64);
+
+        /*
+         * Secrets without escapes are found in both. Those with escaped
+         * optional parts, such as padding, are found shorter in data.
+         */
+        let found = matches.iter().position(|other| {
+            other.name() == scan_match.name()
+                && other.start() < scan_match.start() + scan_match.len()
+                && scan_match.start() < other.start() + other.len()
+        });
+
+        match found {
+            Some(index) if matches[index].len() < scan_match.len() => { matches[index] = scan_match; },
+            Some(_) => {},
+            None => { matches.push(scan_match); },
+        }
+    }
+
+    matches.sort_by_key(|scan_match| scan_match.start());
+
+    matches
+}
@@ -0,0 +1,581 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+use super::redaction::{self, RedactionTemplate, DEFAULT_REDACTION_TOKEN};
+
+/* Appended to the file name of backups */
+pub const BACKUP_EXTENSION: &str = "bak";
+
+/* How much of a file is looked at to tell its encoding */
+const SNIFF_LEN: usize = 8 * 1024;
+
+/* UTF16 without a byte order mark has at least one ASCII character in this many */
+const UTF16_MIN_ASCII_RATIO: usize = 8;
+
+/* and this many times as many NULs at one parity as at the other */
+const UTF16_MIN_NUL_SKEW: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Binary,
+}
+
+impl FileEncoding {
+    /*
+     * UTF16 files have a byte order mark or NULs in the high bytes of ASCII
+     * characters, so nearly all of their NULs are at odd offsets in little
+     * endian and even ones in big endian. Other characters, such as CJK
+     * ones, only have NUL bytes now and then. Binary files have NULs at
+     * both, or too few to tell.
+     */
+    pub fn detect(data: &[u8]) -> Self {
+        match data {
+            [0xFF, 0xFE, ..] => { return Self::Utf16Le; },
+            [0xFE, 0xFF, ..] => { return Self::Utf16Be; },
+            _ => { },
+        }
+
+        let sample = &data[..data.len().min(SNIFF_LEN)];
+
+        if !sample.contains(&0) {
+            return Self::Utf8;
+        }
+
+        if !sample.len().is_multiple_of(2) {
+            return Self::Binary;
+        }
+
+        let mut nuls = [0; 2];
+
+        for (i, b) in sample.iter().enumerate() {
+            if *b == 0 {
+                nuls[i & 1] += 1;
+            }
+        }
+
+        let units = sample.len() / 2;
+        let is_utf16 = |nuls: usize, others: usize| -> bool {
+            nuls * UTF16_MIN_ASCII_RATIO >= units && others * UTF16_MIN_NUL_SKEW <= nuls
+        };
+
+        if is_utf16(nuls[1], nuls[0]) {
+            return Self::Utf16Le;
+        }
+
+        if is_utf16(nuls[0], nuls[1]) {
+            return Self::Utf16Be;
+        }
+
+        Self::Binary
+    }
+
+    /* A line feed in this encoding, binary files are treated as UTF8 */
+    fn line_feed(&self) -> &'static [u8] {
+        match self {
+            Self::Utf16Le => { &[b'\n', 0] },
+            Self::Utf16Be => { &[0, b'\n'] },
+            _ => { b"\n" },
+        }
+    }
+
+    fn decode(
+        &self,
+        data: &[u8]) -> String {
+        match self {
+            Self::Utf16Le | Self::Utf16Be => {
+                let units: Vec<u16> = data
+                    .chunks_exact(2)
+                    .map(|unit| match self {
+                        Self::Utf16Be => { u16::from_be_bytes([unit[0], unit[1]]) },
+                        _ => { u16::from_le_bytes([unit[0], unit[1]]) },
+                    })
+                    .collect();
+
+                String::from_utf16_lossy(&units)
+            },
+            _ => { String::from_utf8_lossy(data).into_owned() },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileRedactionOptions {
+    dry_run: bool,
+    backup: bool,
+    force: bool,
+    template: RedactionTemplate,
+}
+
+impl FileRedactionOptions {
+    /* Only reports what would be redacted, with a diff */
+    pub fn with_dry_run(
+        self,
+        dry_run: bool) -> Self {
+        let mut clone = self;
+
+        clone.dry_run = dry_run;
+
+        clone
+    }
+
+    /*
+     * Keeps the original of redacted files next to them, see
+     * BACKUP_EXTENSION. Files whose backup already exists fail with
+     * AlreadyExists and are left as they are.
+     */
+    pub fn with_backup(
+        self,
+        backup: bool) -> Self {
+        let mut clone = self;
+
+        clone.backup = backup;
+
+        clone
+    }
+
+    /* Also redacts binary files, which are skipped otherwise */
+    pub fn with_force(
+        self,
+        force: bool) -> Self {
+        let mut clone = self;
+
+        clone.force = force;
+
+        clone
+    }
+
+    pub fn with_template(
+        self,
+        template: RedactionTemplate) -> Self {
+        let mut clone = self;
+
+        clone.template = template;
+
+        clone
+    }
+
+    pub fn dry_run(&self) -> bool { self.dry_run }
+
+    pub fn backup(&self) -> bool { self.backup }
+
+    pub fn force(&self) -> bool { self.force }
+
+    pub fn template(&self) -> &RedactionTemplate { &self.template }
+}
+
+impl Default for FileRedactionOptions {
+    /* Redacts text files in place with DEFAULT_REDACTION_TOKEN, no backup */
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            backup: false,
+            force: false,
+            template: DEFAULT_REDACTION_TOKEN.parse().expect("Token has no placeholders"),
+        }
+    }
+}
+
+pub struct FileRedactionReport {
+    encoding: FileEncoding,
+    matches: Vec<ScanMatch>,
+    skipped: bool,
+    written: bool,
+    backup_path: Option<PathBuf>,
+    diff: String,
+}
+
+impl FileRedactionReport {
+    pub fn encoding(&self) -> FileEncoding { self.encoding }
+
+    /* What was found, also for dry runs */
+    pub fn matches(&self) -> &[ScanMatch] { &self.matches }
+
+    /* Binary files are skipped unless forced */
+    pub fn is_skipped(&self) -> bool { self.skipped }
+
+    /* Whether the file was rewritten */
+    pub fn is_written(&self) -> bool { self.written }
+
+    pub fn backup_path(&self) -> Option<&Path> { self.backup_path.as_deref() }
+
+    /*
+     * The changed lines as a unified diff, empty when nothing was found.
+     * The removed lines show the secrets.
+     */
+    pub fn diff(&self) -> &str { &self.diff }
+}
+
+/* Start of the line containing pos and end of the line containing end */
+fn line_bounds(
+    data: &[u8],
+    line_feed: &[u8],
+    pos: usize,
+    end: usize) -> (usize, usize) {
+    let unit = line_feed.len();
+    let is_line_feed = |i: usize| data.get(i..i + unit) == Some(line_feed);
+
+    let mut start = pos - pos % unit;
+
+    while start >= unit && !is_line_feed(start - unit) {
+        start -= unit;
+    }
+
+    let mut stop = end.div_ceil(unit) * unit;
+
+    while stop < data.len() && !is_line_feed(stop) {
+        stop += unit;
+    }
+
+    (start, stop.min(data.len()))
+}
+
+/* Lines of context around each hunk, as diff -u and git diff show */
+const DIFF_CONTEXT: usize = 3;
+
+/* A run of changed lines, starting at the zero based line first */
+struct DiffChange {
+    first: usize,
+    removed: usize,
+    added: Vec<String>,
+}
+
+/*
+ * Writes lines with prefix, carriage returns included so the diff applies.
+ * The last line of a file without a final line feed is marked as such.
+ */
+fn diff_lines<'a>(
+    diff: &mut String,
+    prefix: char,
+    lines: impl Iterator<Item = &'a str>,
+    open_end: bool) {
+    let mut any = false;
+
+    for line in lines {
+        diff.push(prefix);
+        diff.push_str(line);
+        diff.push('\n');
+        any = true;
+    }
+
+    if any && open_end {
+        diff.push_str("\\ No newline at end of file\n");
+    }
+}
+
+/* The runs of lines with redactions, with their redacted text */
+fn diff_changes(
+    data: &[u8],
+    encoding: FileEncoding,
+    redactions: &[(usize, usize, Vec<u8>)]) -> Vec<DiffChange> {
+    let line_feed = encoding.line_feed();
+    let mut changes = Vec::new();
+    let mut i = 0;
+    let mut line = 0;
+    let mut counted = 0;
+
+    while i < redactions.len() {
+        let (hunk_start, mut hunk_end) = line_bounds(data, line_feed, redactions[i].0, redactions[i].1);
+        let mut redacted = data[hunk_start..redactions[i].0].to_vec();
+        let mut index = redactions[i].0;
+
+        /* Redactions on the same or adjacent lines share a change */
+        while i < redactions.len() {
+            let (start, end, token) = &redactions[i];
+            let (line_start, line_end) = line_bounds(data, line_feed, *start, *end);
+
+            if line_start > hunk_end + line_feed.len() {
+                break;
+            }
+
+            redacted.extend_from_slice(&data[index..(*start).max(index)]);
+            redacted.extend_from_slice(token);
+            index = index.max(*end);
+            hunk_end = hunk_end.max(line_end);
+            i += 1;
+        }
+
+        redacted.extend_from_slice(&data[index..hunk_end]);
+
+        line += data[counted..hunk_start]
+            .chunks_exact(line_feed.len())
+            .filter(|unit| *unit == line_feed)
+            .count();
+        counted = hunk_start;
+
+        let removed = encoding.decode(&data[hunk_start..hunk_end]).split('\n').count();
+        let added = encoding.decode(&redacted).split('\n').map(String::from).collect();
+
+        changes.push(DiffChange { first: line, removed, added });
+    }
+
+    changes
+}
+
+/*
+ * The redactions as a unified diff with DIFF_CONTEXT lines of context, so
+ * patch and git apply can apply it to the original.
+ */
+fn unified_diff(
+    path: &Path,
+    data: &[u8],
+    encoding: FileEncoding,
+    redactions: &[(usize, usize, Vec<u8>)]) -> String {
+    if redactions.is_empty() {
+        return String::new();
+    }
+
+    if encoding == FileEncoding::Binary {
+        return format!("Binary file {} differs\n", path.display());
+    }
+
+    let text = encoding.decode(data);
+    let open_end = !data.ends_with(encoding.line_feed());
+    let mut lines: Vec<&str> = text.split('\n').collect();
+
+    if !open_end {
+        lines.pop();
+    }
+
+    let changes = diff_changes(data, encoding, redactions);
+    let mut diff = format!("--- {}\n+++ {}\n", path.display(), path.display());
+    let mut i = 0;
+
+    /* Redacted secrets can span lines, so the redacted file can have fewer */
+    let mut added_lines: isize = 0;
+
+    while i < changes.len() {
+        /* Changes whose context would touch share a hunk */
+        let mut last = i;
+
+        while last + 1 < changes.len() &&
+              changes[last + 1].first <= changes[last].first + changes[last].removed + 2 * DIFF_CONTEXT {
+            last += 1;
+        }
+
+        let start = changes[i].first.saturating_sub(DIFF_CONTEXT);
+        let end = (changes[last].first + changes[last].removed + DIFF_CONTEXT).min(lines.len());
+        let mut hunk = String::new();
+        let mut line = start;
+        let mut new_lines = 0;
+
+        for change in &changes[i..=last] {
+            diff_lines(&mut hunk, ' ', lines[line..change.first].iter().copied(), false);
+
+            let change_end = change.first + change.removed;
+            let at_end = open_end && change_end == lines.len();
+
+            diff_lines(&mut hunk, '-', lines[change.first..change_end].iter().copied(), at_end);
+            diff_lines(&mut hunk, '+', change.added.iter().map(String::as_str), at_end);
+
+            new_lines += change.first - line + change.added.len();
+            line = change_end;
+        }
+
+        diff_lines(&mut hunk, ' ', lines[line..end].iter().copied(), open_end && end == lines.len());
+        new_lines += end - line;
+
+        let old_lines = end - start;
+
+        diff.push_str(
+            &format!(
+                "@@ -{},{} +{},{} @@\n",
+                start + 1,
+                old_lines,
+                (start + 1) as isize + added_lines,
+                new_lines));
+        diff.push_str(&hunk);
+
+        added_lines += new_lines as isize - old_lines as isize;
+        i = last + 1;
+    }
+
+    diff
+}
+
+/*
+ * Gives the file at path the owner and group in metadata. Only root can
+ * give files away, so otherwise just the group is tried, and not being
+ * allowed either leaves the file to whoever wrote it.
+ */
+#[cfg(unix)]
+fn keep_owner(
+    path: &Path,
+    metadata: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    match chown(path, Some(metadata.uid()), Some(metadata.gid())) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let _ = chown(path, None, Some(metadata.gid()));
+            Ok(())
+        },
+        result => { result },
+    }
+}
+
+#[cfg(not(unix))]
+fn keep_owner(
+    _path: &Path,
+    _metadata: &fs::Metadata) -> io::Result<()> {
+    Ok(())
+}
+
+/*
+ * Writes data next to path and renames it over path, so readers see the
+ * old or the new file and never part of one. Symbolic links are followed,
+ * the file they point to is replaced and they are left as they are. The
+ * permissions are kept, and on Unix the owner and group as far as the
+ * process is allowed to set them, see keep_owner().
+ */
+pub(crate) fn write_atomically(
+    path: &Path,
+    data: &[u8]) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+    let metadata = fs::metadata(&path)?;
+
+    let result = create_new_like(&temp_path, &metadata)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        /* Changing the owner can clear setuid bits, so it goes first */
+        .and_then(|_| keep_owner(&temp_path, &metadata))
+        .and_then(|_| fs::set_permissions(&temp_path, metadata.permissions()))
+        .and_then(|_| fs::rename(&temp_path, &path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/*
+ * Creates path, which must not exist, no more accessible than the file
+ * metadata is for, so secrets written to it are never readable by more
+ * users, not even until the permissions are copied.
+ */
+fn create_new_like(
+    path: &Path,
+    metadata: &fs::Metadata) -> io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(metadata.permissions().mode() & 0o777);
+    }
+
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    options.open(path)
+}
+
+/* path with BACKUP_EXTENSION appended, such as app.config.bak */
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+
+    backup.push(".");
+    backup.push(BACKUP_EXTENSION);
+
+    PathBuf::from(backup)
+}
+
+/*
+ * Writes the original of path to backup, with the same permissions. An
+ * existing backup is never overwritten, it may be the only copy of an
+ * earlier original.
+ */
+fn write_backup(
+    path: &Path,
+    backup: &Path,
+    data: &[u8]) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+
+    let mut file = create_new_like(backup, &metadata)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => {
+                io::Error::new(e.kind(), format!("Backup {} already exists", backup.display()))
+            },
+            _ => { e },
+        })?;
+
+    let result = file
+        .write_all(data)
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::set_permissions(backup, metadata.permissions()));
+
+    if result.is_err() {
+        let _ = fs::remove_file(backup);
+    }
+
+    result
+}
+
+/*
+ * Redacts what engine finds in the file at path, rewriting it atomically.
+ * UTF8 and UTF16 files are scanned as they are, secrets are replaced in
+ * the encoding they were found in. Files without secrets are left alone.
+ */
+pub fn redact_file(
+    engine: &ScanEngine,
+    path: impl AsRef<Path>,
+    options: &FileRedactionOptions) -> io::Result<FileRedactionReport> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let encoding = FileEncoding::detect(&data);
+
+    let mut report = FileRedactionReport {
+        encoding,
+        matches: Vec::new(),
+        skipped: false,
+        written: false,
+        backup_path: None,
+        diff: String::new(),
+    };
+
+    if encoding == FileEncoding::Binary && !options.force {
+        report.skipped = true;
+        return Ok(report);
+    }
+
+    report.matches = engine.find_matches(&data, false);
+
+    let big_endian = encoding == FileEncoding::Utf16Be;
+    let redactions = redaction::template_redactions(&data, &report.matches, big_endian, &options.template);
+    report.diff = unified_diff(path, &data, encoding, &redactions);
+
+    if redactions.is_empty() || options.dry_run {
+        return Ok(report);
+    }
+
+    let redacted = redaction::apply_redactions(&data, &redactions);
+
+    if options.backup {
+        let backup = backup_path(path);
+
+        write_backup(path, &backup, &data)?;
+        report.backup_path = Some(backup);
+    }
+
+    write_atomically(path, &redacted)?;
+    report.written = true;
+
+    Ok(report)
+}
@@ -0,0 +1,217 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::sync::OnceLock;
+
+use super::identifiable_scans::{ScanDefinition, ScanEngine, ScanMatch, ScanOptions};
+use super::validators::ValidatorSpec;
+
+/*
+ * These tables mirror CompiledHighPerformancePattern.Generated.cs, which is
+ * generated from the .NET rules that implement IHighPerformanceScannableKey.
+ * Keep both in the same order as the generated .NET code. A unit test checks
+ * them against the generated file so they cannot drift apart.
+ *
+ * The .NET table is keyed by signature only. The rule ID and the signature
+ * character the ScanEngine vectorizes on are Rust additions.
+ */
+const RAW_REGEXES: [&str; 13] = [
+    r"^[a-zA-Z0-9]{52}.{4}9(?:9|D|H)[a-zA-Z0-9][A-L][a-zA-Z0-9]{16}[A-Za-z][a-zA-Z0-9]{7}(?:[a-zA-Z0-9]{2}==)?",
+    r"^[~.a-zA-Z0-9_\-]{3}.{3}[~.a-zA-Z0-9_\-]{31}",
+    r"^[~.a-zA-Z0-9_\-]{3}.{3}[~.a-zA-Z0-9_\-]{34}",
+    r"^[a-zA-Z0-9_\-]{44}.{4}[a-zA-Z0-9_\-]{5}[AQgw]==",
+    r"^[a-zA-Z0-9]{42}.{4}[A-D][a-zA-Z0-9]{5}",
+    r"^[a-zA-Z0-9+/]{33}.{4}[A-P][a-zA-Z0-9+/]{5}=",
+    r"^[a-zA-Z0-9+/]{76}.{4}[a-zA-Z0-9+/]{5}[AQgw]==",
+    r"^[a-zA-Z0-9+/]{42}.{4}[A-D][a-zA-Z0-9+/]{5}",
+    r"^.{3}[a-z2-7]{43}",
+    r"^.{3}[A-Z2-7]{43}",
+    r"^.{4}[0-9a-f\-]{32,34}",
+    r"^.{4}[a-zA-Z0-9]{36}",
+    r"^secret_scanning_.{4}fc6f8d7638cf1c11da812da308d43_[0-9A-Za-z]{5}",
+];
+
+/* Uncompiled version of CompiledHighPerformancePattern */
+struct HighPerformancePattern {
+    id: &'static str,
+    signature: &'static str,
+    sig_char: u8,
+    signature_prefix_length: usize,
+    min_match_length: usize,
+    max_match_length: usize,
+    regex: usize,
+}
+
+impl HighPerformancePattern {
+    const fn new(
+        id: &'static str,
+        signature: &'static str,
+        sig_char: u8,
+        signature_prefix_length: usize,
+        min_match_length: usize,
+        max_match_length: usize,
+        regex: usize) -> Self {
+        Self {
+            id,
+            signature,
+            sig_char,
+            signature_prefix_length,
+            min_match_length,
+            max_match_length,
+            regex,
+        }
+    }
+}
+
+const PATTERNS: [HighPerformancePattern; 22] = [
+    HighPerformancePattern::new("SEC101/163", "+ABa", b'A', 76, 88, 88, 6),
+    HighPerformancePattern::new("SEC101/176", "+ACR", b'A', 42, 52, 52, 7),
+    HighPerformancePattern::new("SEC101/172", "+AEh", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/170", "+AMC", b'A', 76, 88, 88, 6),
+    HighPerformancePattern::new("SEC101/173", "+ARm", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/171", "+ASb", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/152", "+ASt", b'A', 76, 88, 88, 6),
+    HighPerformancePattern::new("SEC101/156", "7Q~", b'Q', 3, 37, 37, 1),
+    HighPerformancePattern::new("SEC101/156", "8Q~", b'Q', 3, 40, 40, 2),
+    HighPerformancePattern::new("SEC101/565", "ab85", b'8', 16, 55, 55, 12),
+    HighPerformancePattern::new("SEC101/160", "ACDb", b'A', 76, 88, 88, 6),
+    HighPerformancePattern::new("SEC101/178", "AIoT", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/181", "APIM", b'A', 76, 88, 88, 6),
+    HighPerformancePattern::new("SEC101/154", "AzCa", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/199", "AZEG", b'A', 33, 44, 44, 5),
+    HighPerformancePattern::new("SEC101/158", "AzFu", b'A', 44, 56, 56, 3),
+    HighPerformancePattern::new("SEC101/166", "AzSe", b'A', 42, 52, 52, 4),
+    HighPerformancePattern::new("SEC101/110", "dapi", b'p', 0, 36, 38, 10),
+    HighPerformancePattern::new("SEC101/200", "JQQJ", b'Q', 52, 84, 88, 0),
+    HighPerformancePattern::new("SEC101/050", "npm_", b'_', 0, 40, 40, 11),
+    HighPerformancePattern::new("SEC101/031", "oy2", b'2', 0, 46, 46, 8),
+    HighPerformancePattern::new("SEC101/031", "OY2", b'2', 0, 46, 46, 9),
+];
+
+static COMPILED_PATTERNS: OnceLock<Vec<CompiledHighPerformancePattern>> = OnceLock::new();
+
+/*
+ * A pattern that HighPerformanceScanner can scan for. There is one per
+ * signature, compiled once from the table above.
+ */
+pub struct CompiledHighPerformancePattern {
+    id: &'static str,
+    signature: &'static str,
+    sig_char: u8,
+    signature_prefix_length: usize,
+    min_match_length: usize,
+    max_match_length: usize,
+    scoped_regex: &'static str,
+    spec: ValidatorSpec,
+}
+
+impl CompiledHighPerformancePattern {
+    pub fn all() -> &'static [CompiledHighPerformancePattern] {
+        COMPILED_PATTERNS.get_or_init(|| {
+            let specs: Vec<ValidatorSpec> = RAW_REGEXES
+                .iter()
+                .map(|raw| raw.parse().expect("Valid scoped regex"))
+                .collect();
+
+            PATTERNS
+                .iter()
+                .map(|pattern| Self {
+                    id: pattern.id,
+                    signature: pattern.signature,
+                    sig_char: pattern.sig_char,
+                    signature_prefix_length: pattern.signature_prefix_length,
+                    min_match_length: pattern.min_match_length,
+                    max_match_length: pattern.max_match_length,
+                    scoped_regex: RAW_REGEXES[pattern.regex],
+                    spec: specs[pattern.regex].clone(),
+                })
+                .collect()
+        })
+    }
+
+    pub fn for_signature(signature: &str) -> Option<&'static CompiledHighPerformancePattern> {
+        Self::all()
+            .iter()
+            .find(|pattern| pattern.signature == signature)
+    }
+
+    pub fn id(&self) -> &'static str { self.id }
+
+    pub fn signature(&self) -> &'static str { self.signature }
+
+    /* Fixed number of characters before the signature in a match */
+    pub fn signature_prefix_length(&self) -> usize { self.signature_prefix_length }
+
+    pub fn min_match_length(&self) -> usize { self.min_match_length }
+
+    pub fn max_match_length(&self) -> usize { self.max_match_length }
+
+    /* The regex exactly as written in the .NET table */
+    pub fn scoped_regex(&self) -> &'static str { self.scoped_regex }
+
+    /* The scoped regex compiled to a declarative validator */
+    pub fn validator_spec(&self) -> &ValidatorSpec { &self.spec }
+
+    /*
+     * Runs the scoped regex on data starting at the match start, which
+     * assumes the signature is at the expected location. Returns the
+     * length of the match or 0 if none.
+     */
+    pub fn match_len(
+        &self,
+        data: &[u8]) -> usize {
+        let data = &data[..data.len().min(self.max_match_length)];
+
+        match self.spec.match_len(data) {
+            Some(len) if len >= self.min_match_length => { len },
+            _ => { 0 },
+        }
+    }
+
+    pub fn scan_definition(&self) -> ScanDefinition {
+        ScanDefinition::from_spec(
+            self.id,
+            self.signature.as_bytes(),
+            self.sig_char,
+            (self.signature_prefix_length + self.signature.len()) as u64,
+            self.spec.clone())
+        .with_min_len(self.min_match_length)
+    }
+}
+
+/*
+ * Scans for compiled high-performance patterns, producing the same results
+ * as the .NET HighPerformanceScanner for the same input.
+ */
+pub struct HighPerformanceScanner {
+    engine: ScanEngine,
+}
+
+impl HighPerformanceScanner {
+    pub fn new(patterns: impl IntoIterator<Item = &'static CompiledHighPerformancePattern>) -> Self {
+        let mut options = ScanOptions::empty();
+
+        for pattern in patterns {
+            options = options.with_definition(pattern.scan_definition());
+        }
+
+        Self {
+            engine: ScanEngine::new(options),
+        }
+    }
+
+    pub fn engine(&self) -> &ScanEngine { &self.engine }
+
+    pub fn scan(
+        &self,
+        input: &[u8],
+        want_text: bool) -> Vec<ScanMatch> {
+        self.engine.find_matches(input, want_text)
+    }
+}
+
+impl Default for HighPerformanceScanner {
+    fn default() -> Self {
+        Self::new(CompiledHighPerformancePattern::all())
+    }
+}
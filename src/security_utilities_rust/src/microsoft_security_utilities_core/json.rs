@@ -0,0 +1,407 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+/*
+ * Minimal JSON reader for the small documents found inside secrets, such
+ * as JWT headers and claims, and for locating the string values of larger
+ * ones. It only reads, and rejects anything nested deeper than MAX_DEPTH so
+ * untrusted input can't exhaust the stack.
+ */
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    /* Members in document order */
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut reader = Reader::new(text, false);
+
+        let value = reader.value(0)?;
+        reader.skip_whitespace();
+
+        match reader.pos == reader.text.len() {
+            true => { Some(value) },
+            false => { None },
+        }
+    }
+
+    /* First member with the name, if this is an object */
+    pub fn get(
+        &self,
+        name: &str) -> Option<&JsonValue> {
+        self.as_object()?
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => { Some(*value) },
+            _ => { None },
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => { Some(*value) },
+            _ => { None },
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => { Some(value) },
+            _ => { None },
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => { Some(values) },
+            _ => { None },
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(members) => { Some(members) },
+            _ => { None },
+        }
+    }
+
+    pub fn is_null(&self) -> bool { *self == JsonValue::Null }
+}
+
+/* A string value in a document, not a member name */
+pub(crate) struct JsonString {
+    /* RFC 6901 JSON pointer to the value */
+    pub(crate) pointer: String,
+    /* The literal, quotes included */
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) value: String,
+}
+
+/* Every string value in text in document order, None if it isn't JSON */
+pub(crate) fn string_values(text: &str) -> Option<Vec<JsonString>> {
+    let mut reader = Reader::new(text, true);
+
+    reader.value(0)?;
+    reader.skip_whitespace();
+
+    match reader.pos == reader.text.len() {
+        true => { Some(reader.strings) },
+        false => { None },
+    }
+}
+
+/* A path segment of a JSON pointer, with '~' and '/' escaped */
+pub(crate) fn pointer_segment(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+struct Reader<'a> {
+    text: &'a [u8],
+    pos: usize,
+    /* Whether to record string values and their pointers */
+    record: bool,
+    path: Vec<String>,
+    strings: Vec<JsonString>,
+}
+
+impl Reader<'_> {
+    fn new(
+        text: &str,
+        record: bool) -> Reader<'_> {
+        Reader {
+            text: text.as_bytes(),
+            pos: 0,
+            record,
+            path: Vec::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    fn record_string(
+        &mut self,
+        start: usize,
+        value: &str) {
+        if self.record {
+            self.strings.push(JsonString {
+                pointer: self.path.iter().map(|segment| format!("/{}", segment)).collect(),
+                start,
+                end: self.pos,
+                value: value.to_string(),
+            });
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\r' | b'\n') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn expect(
+        &mut self,
+        literal: &[u8]) -> Option<()> {
+        if !self.text[self.pos..].starts_with(literal) {
+            return None;
+        }
+
+        self.pos += literal.len();
+
+        Some(())
+    }
+
+    fn value(
+        &mut self,
+        depth: usize) -> Option<JsonValue> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        self.skip_whitespace();
+
+        match self.peek()? {
+            b'n' => { self.expect(b"null").map(|_| JsonValue::Null) },
+            b't' => { self.expect(b"true").map(|_| JsonValue::Bool(true)) },
+            b'f' => { self.expect(b"false").map(|_| JsonValue::Bool(false)) },
+            b'"' => {
+                let start = self.pos;
+                let value = self.string()?;
+
+                self.record_string(start, &value);
+
+                Some(JsonValue::String(value))
+            },
+            b'[' => { self.array(depth) },
+            b'{' => { self.object(depth) },
+            _ => { self.number() },
+        }
+    }
+
+    fn array(
+        &mut self,
+        depth: usize) -> Option<JsonValue> {
+        self.pos += 1;
+
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Some(JsonValue::Array(values));
+        }
+
+        loop {
+            if self.record {
+                self.path.push(values.len().to_string());
+            }
+
+            values.push(self.value(depth + 1)?);
+            self.path.pop();
+            self.skip_whitespace();
+
+            match self.peek()? {
+                b',' => { self.pos += 1; },
+                b']' => {
+                    self.pos += 1;
+                    return Some(JsonValue::Array(values));
+                },
+                _ => { return None; },
+            }
+        }
+    }
+
+    fn object(
+        &mut self,
+        depth: usize) -> Option<JsonValue> {
+        self.pos += 1;
+
+        let mut members = Vec::new();
+
+        self.skip_whitespace();
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Some(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            if self.peek()? != b'"' {
+                return None;
+            }
+
+            let name = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(b":")?;
+
+            if self.record {
+                self.path.push(pointer_segment(&name));
+            }
+
+            let value = self.value(depth + 1)?;
+
+            self.path.pop();
+            members.push((name, value));
+            self.skip_whitespace();
+
+            match self.peek()? {
+                b',' => { self.pos += 1; },
+                b'}' => {
+                    self.pos += 1;
+                    return Some(JsonValue::Object(members));
+                },
+                _ => { return None; },
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        /* Skip the quote */
+        self.pos += 1;
+
+        let mut bytes = Vec::new();
+
+        loop {
+            let b = self.peek()?;
+            self.pos += 1;
+
+            match b {
+                b'"' => { break; },
+                b'\\' => {
+                    let escaped = self.peek()?;
+                    self.pos += 1;
+
+                    let c = match escaped {
+                        b'"' => { '"' },
+                        b'\\' => { '\\' },
+                        b'/' => { '/' },
+                        b'b' => { '\u{8}' },
+                        b'f' => { '\u{c}' },
+                        b'n' => { '\n' },
+                        b'r' => { '\r' },
+                        b't' => { '\t' },
+                        b'u' => { self.unicode_escape()? },
+                        _ => { return None; },
+                    };
+
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                },
+                0..=0x1F => { return None; },
+                _ => { bytes.push(b); },
+            }
+        }
+
+        String::from_utf8(bytes).ok()
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let hex = self.text.get(self.pos..self.pos + 4)?;
+        let value = u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+
+        self.pos += 4;
+
+        Some(value)
+    }
+
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+
+        /* Surrogate pairs are two escapes */
+        if (0xD800..0xDC00).contains(&high) {
+            self.expect(b"\\u")?;
+
+            let low = self.hex4()?;
+
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+
+            return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+        }
+
+        char::from_u32(high)
+    }
+
+    fn number(&mut self) -> Option<JsonValue> {
+        let start = self.pos;
+
+        while let Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') = self.peek() {
+            self.pos += 1;
+        }
+
+        if start == self.pos {
+            return None;
+        }
+
+        std::str::from_utf8(&self.text[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()
+            .map(JsonValue::Number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_parse() {
+        let value = JsonValue::parse(
+            r#" {"alg":"HS256", "n": -1.5e2, "ok": true, "none": null,
+                "aud": ["a", "b"], "esc": "\"\u00e9\ud83d\ude00\n"} "#).unwrap();
+
+        assert_eq!(Some("HS256"), value.get("alg").and_then(|v| v.as_str()));
+        assert_eq!(Some(-150.0), value.get("n").and_then(|v| v.as_f64()));
+        assert_eq!(Some(true), value.get("ok").and_then(|v| v.as_bool()));
+        assert!(value.get("none").unwrap().is_null());
+        assert_eq!(2, value.get("aud").and_then(|v| v.as_array()).unwrap().len());
+        assert_eq!(Some("\"\u{e9}\u{1F600}\n"), value.get("esc").and_then(|v| v.as_str()));
+        assert!(value.get("missing").is_none());
+    }
+
+    #[test]
+    fn json_parse_invalid() {
+        let cases = [
+            "",
+            "{",
+            "{\"a\"}",
+            "{\"a\":1,}",
+            "[1 2]",
+            "\"unterminated",
+            "\"\\x\"",
+            "\"\\ud800\"",
+            "tru",
+            "{} {}",
+        ];
+
+        for (i, case) in cases.iter().enumerate() {
+            assert!(JsonValue::parse(case).is_none(), "Case {}: {}", i, case);
+        }
+
+        /* Nesting is limited */
+        let deep = format!("{}{}", "[".repeat(MAX_DEPTH + 2), "]".repeat(MAX_DEPTH + 2));
+        assert!(JsonValue::parse(&deep).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::alphabet::URL_SAFE;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine as _;
+
+use super::identifiable_scans::{ScanDefinition, Validation};
+use super::json::JsonValue;
+
+pub const JWT_RULE_ID: &str = "SEC101/528";
+
+/*
+ * Port of the .NET UnclassifiedJwt rule:
+ * (?:^|[^0-9A-Za-z-_.])e[0-9A-Za-z-_=]{23,}\.e[0-9A-Za-z-_=]{23,}\.[0-9A-Za-z-_=]{24,}(?:[^0-9A-Za-z-_]|$)
+ * and the header must decode to something with an "alg" field.
+ */
+const JWT_MIN_LEN: usize = 24 + 1 + 24 + 1 + 24;
+
+/* Tokens longer than this aren't reported, they can't be told apart from truncated ones */
+const JWT_MAX_LEN: usize = 8 * 1024;
+
+/*
+ * Base64 encodings of '{"', '{ "' and '{\r\n', how every JWT begins. The
+ * signature characters are the least common letters of each.
+ */
+const JWT_SIGNATURES: [(&[u8], u8); 3] = [
+    (b"eyJ", b'J'),
+    (b"eyAi", b'A'),
+    (b"ewog", b'w'),
+];
+
+/* .NET pads before decoding, trailing bits are tolerated the same way */
+const JWT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true));
+
+fn is_jwt_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'='
+}
+
+fn run_len(data: &[u8]) -> usize {
+    data.iter().take_while(|b| is_jwt_char(**b)).count()
+}
+
+fn decode_segment(segment: &str) -> Option<Vec<u8>> {
+    /* Padding may only be at the end */
+    let trimmed = segment.trim_end_matches('=');
+
+    if trimmed.contains('=') {
+        return None;
+    }
+
+    JWT_BASE64.decode(trimmed).ok()
+}
+
+fn validate(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    /* Must not continue another token */
+    if anchor > 0 {
+        let b = data[anchor - 1];
+
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.' {
+            return None;
+        }
+    }
+
+    let mut end = anchor;
+    let mut header_end = anchor;
+
+    for i in 0..3 {
+        if i != 0 {
+            if data.get(end) != Some(&b'.') {
+                return None;
+            }
+
+            end += 1;
+
+            /* Header and payload are JSON objects */
+            if i == 1 && data.get(end) != Some(&b'e') {
+                return None;
+            }
+        }
+
+        /* Each segment is at least 24 characters */
+        let len = run_len(&data[end..]);
+
+        if len < 24 {
+            return None;
+        }
+
+        end += len;
+
+        if i == 0 {
+            header_end = end;
+        }
+    }
+
+    if end - anchor >= JWT_MAX_LEN {
+        return None;
+    }
+
+    let header = std::str::from_utf8(&data[anchor..header_end]).ok()?;
+    let decoded = decode_segment(header)?;
+
+    if !decoded.windows(5).any(|w| w == b"\"alg\"") {
+        return None;
+    }
+
+    Some(Validation::new(anchor, end - anchor))
+}
+
+/*
+ * Decoded view of a JWT, such as one found by the SEC101/528 rule. The
+ * signature is not verified.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jwt {
+    header: JsonValue,
+    claims: Option<JsonValue>,
+}
+
+impl Jwt {
+    /* Requires a JSON object header, the claims are optional as with JWE */
+    pub fn parse(token: &str) -> Option<Self> {
+        let mut segments = token.split('.');
+
+        let header = segments.next()?;
+        let payload = segments.next()?;
+
+        /* The signature, which isn't decoded */
+        segments.next()?;
+
+        let decode = |segment: &str| -> Option<JsonValue> {
+            let decoded = decode_segment(segment)?;
+            let value = JsonValue::parse(std::str::from_utf8(&decoded).ok()?)?;
+
+            value.as_object()?;
+
+            Some(value)
+        };
+
+        Some(Self {
+            header: decode(header)?,
+            claims: decode(payload),
+        })
+    }
+
+    pub fn scan_definitions() -> Vec<ScanDefinition> {
+        JWT_SIGNATURES
+            .iter()
+            .map(|(sig, sig_char)| {
+                ScanDefinition::with_context(
+                    JWT_RULE_ID,
+                    sig,
+                    *sig_char,
+                    sig.len() as u64,
+                    JWT_MAX_LEN,
+                    1,
+                    validate)
+                .with_min_len(JWT_MIN_LEN)
+            })
+            .collect()
+    }
+
+    pub fn header(&self) -> &JsonValue { &self.header }
+
+    pub fn header_claim(
+        &self,
+        name: &str) -> Option<&JsonValue> {
+        self.header.get(name)
+    }
+
+    pub fn alg(&self) -> Option<&str> {
+        self.header_claim("alg")?.as_str()
+    }
+
+    /* None when the payload isn't a JSON object */
+    pub fn claims(&self) -> Option<&JsonValue> { self.claims.as_ref() }
+
+    pub fn claim(
+        &self,
+        name: &str) -> Option<&JsonValue> {
+        self.claims.as_ref()?.get(name)
+    }
+
+    /* Expiration in seconds since the Unix epoch */
+    pub fn exp(&self) -> Option<u64> {
+        let exp = self.claim("exp")?.as_f64()?;
+
+        match exp >= 0.0 {
+            true => { Some(exp as u64) },
+            false => { None },
+        }
+    }
+
+    pub fn iss(&self) -> Option<&str> {
+        self.claim("iss")?.as_str()
+    }
+
+    /* The audience can be a single string or an array of them */
+    pub fn aud(&self) -> Vec<&str> {
+        match self.claim("aud") {
+            Some(JsonValue::String(aud)) => { vec![aud.as_str()] },
+            Some(JsonValue::Array(auds)) => { auds.iter().filter_map(|aud| aud.as_str()).collect() },
+            _ => { Vec::new() },
+        }
+    }
+
+    /* Tokens without an expiration never expire */
+    pub fn is_expired_at(
+        &self,
+        now: SystemTime) -> bool {
+        let exp = match self.exp() {
+            Some(exp) => { exp },
+            None => { return false; },
+        };
+
+        match now.duration_since(UNIX_EPOCH) {
+            Ok(now) => { now.as_secs() >= exp },
+            Err(_) => { false },
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use super::identifiable_scans::ScanMatch;
+use super::redaction;
+
+/* Literals have no rule, like .NET detections of kind Literal */
+pub const SECRET_LITERAL_RULE_ID: &str = "SecretLiteral";
+
+/* Same as the .NET redaction token for literals */
+pub const DEFAULT_LITERAL_REDACTION_TOKEN: &str = "***";
+
+/* Same as the .NET LiteralEncoder delegate, empty results are ignored */
+pub type LiteralEncoder = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/* Same as the .NET WellKnownLiteralEncoders.UnescapeBackslashes */
+pub fn unescape_backslashes(value: &str) -> String {
+    value
+        .replace("\\\\", "\\")
+        .replace("\\'", "'")
+        .replace("\\\"", "\"")
+        .replace("\\t", "\t")
+}
+
+/*
+ * Same as the .NET WellKnownLiteralEncoders.UriDataEscape, which escapes
+ * everything but RFC 3986 unreserved characters.
+ */
+pub fn uri_data_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for b in value.bytes() {
+        match b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            true => { escaped.push(b as char); },
+            false => { escaped.push_str(&format!("%{:02X}", b)); },
+        }
+    }
+
+    escaped
+}
+
+/* Standard base64 with padding */
+pub fn base64_encode(value: &str) -> String {
+    STANDARD.encode(value)
+}
+
+/* The value as it appears in a JSON string, without the quotes */
+pub fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '"' => { escaped.push_str("\\\""); },
+            '\\' => { escaped.push_str("\\\\"); },
+            '\n' => { escaped.push_str("\\n"); },
+            '\r' => { escaped.push_str("\\r"); },
+            '\t' => { escaped.push_str("\\t"); },
+            '\u{08}' => { escaped.push_str("\\b"); },
+            '\u{0C}' => { escaped.push_str("\\f"); },
+            c if (c as u32) < 0x20 => { escaped.push_str(&format!("\\u{:04x}", c as u32)); },
+            c => { escaped.push(c); },
+        }
+    }
+
+    escaped
+}
+
+/* A registered literal or an encoding of one, as UTF8 or UTF16 LE bytes */
+struct Literal {
+    bytes: Vec<u8>,
+    text: Arc<str>,
+    utf16: bool,
+}
+
+struct Node {
+    /* Sorted by byte */
+    next: Vec<(u8, u32)>,
+    fail: u32,
+    /* Literals that end here, including through fail links */
+    outputs: Vec<u32>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            next: Vec::new(),
+            fail: 0,
+            outputs: Vec::new(),
+        }
+    }
+
+    fn get(
+        &self,
+        b: u8) -> Option<u32> {
+        self.next
+            .binary_search_by_key(&b, |(next_b, _)| *next_b)
+            .ok()
+            .map(|i| self.next[i].1)
+    }
+}
+
+/*
+ * Aho-Corasick automaton over all literals, so scanning takes the same time
+ * however many literals there are.
+ */
+struct Automaton {
+    nodes: Vec<Node>,
+    literals: Vec<Literal>,
+}
+
+impl Automaton {
+    fn new(literals: Vec<Literal>) -> Self {
+        let mut nodes = vec![Node::new()];
+
+        for (index, literal) in literals.iter().enumerate() {
+            let mut state = 0;
+
+            for b in &literal.bytes {
+                state = match nodes[state].get(*b) {
+                    Some(next) => { next as usize },
+                    None => {
+                        let next = nodes.len();
+                        let node = &mut nodes[state];
+                        let pos = node.next.partition_point(|(next_b, _)| next_b < b);
+
+                        node.next.insert(pos, (*b, next as u32));
+                        nodes.push(Node::new());
+
+                        next
+                    },
+                };
+            }
+
+            nodes[state].outputs.push(index as u32);
+        }
+
+        /* Breadth first, so fail links always point to finished nodes */
+        let mut queue: VecDeque<usize> = nodes[0]
+            .next
+            .iter()
+            .map(|(_, next)| *next as usize)
+            .collect();
+
+        while let Some(state) = queue.pop_front() {
+            for (b, next) in nodes[state].next.clone() {
+                let mut fail = nodes[state].fail as usize;
+
+                while fail != 0 && nodes[fail].get(b).is_none() {
+                    fail = nodes[fail].fail as usize;
+                }
+
+                let fail = nodes[fail].get(b).unwrap_or(0);
+                let outputs = nodes[fail as usize].outputs.clone();
+                let node = &mut nodes[next as usize];
+
+                node.fail = fail;
+                node.outputs.extend(outputs);
+
+                queue.push_back(next as usize);
+            }
+        }
+
+        Self {
+            nodes,
+            literals,
+        }
+    }
+
+    /* Every occurrence of every literal, including overlapping ones */
+    fn find(
+        &self,
+        data: &[u8],
+        mut found: impl FnMut(usize, &Literal, u32)) {
+        let mut state = 0;
+
+        for (i, b) in data.iter().enumerate() {
+            loop {
+                if let Some(next) = self.nodes[state].get(*b) {
+                    state = next as usize;
+                    break;
+                }
+
+                if state == 0 {
+                    break;
+                }
+
+                state = self.nodes[state].fail as usize;
+            }
+
+            for index in &self.nodes[state].outputs {
+                let literal = &self.literals[*index as usize];
+
+                found(i + 1 - literal.bytes.len(), literal, *index);
+            }
+        }
+    }
+}
+
+/*
+ * Port of the literal masking in the .NET SecretMasker, AddValue() and
+ * AddLiteralEncoder(). Registered values are found as is and encoded by
+ * each encoder, both in UTF8 and UTF16 LE like ScanEngine.
+ */
+#[derive(Clone)]
+pub struct LiteralMasker {
+    min_len: usize,
+    encoders: Vec<LiteralEncoder>,
+    values: Vec<String>,
+    added: HashSet<String>,
+    /* Built on first use after a change */
+    automaton: Arc<OnceLock<Automaton>>,
+}
+
+impl LiteralMasker {
+    /* No encoders, use with_encoder() to add them */
+    pub fn empty() -> Self {
+        Self {
+            min_len: 0,
+            encoders: Vec::new(),
+            values: Vec::new(),
+            added: HashSet::new(),
+            automaton: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /*
+     * Literals shorter than this, in characters, are ignored, like the .NET
+     * MinimumSecretLength. Encodings are checked on their own.
+     */
+    pub fn with_min_len(
+        self,
+        min_len: usize) -> Self {
+        let mut clone = self;
+
+        clone.min_len = min_len;
+        clone.automaton = Arc::new(OnceLock::new());
+
+        clone
+    }
+
+    /* Also finds values as encoded by encoder, including ones already added */
+    pub fn with_encoder(
+        self,
+        encoder: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        let mut clone = self;
+
+        clone.encoders.push(Arc::new(encoder));
+        clone.automaton = Arc::new(OnceLock::new());
+
+        clone
+    }
+
+    /* Registers a secret, empty and already added values are ignored */
+    pub fn add_value(
+        &mut self,
+        value: &str) {
+        if value.is_empty() || !self.added.insert(value.to_string()) {
+            return;
+        }
+
+        self.values.push(value.to_string());
+        self.automaton = Arc::new(OnceLock::new());
+    }
+
+    pub fn min_len(&self) -> usize { self.min_len }
+
+    /* The values added, without their encodings */
+    pub fn values(&self) -> &[String] { &self.values }
+
+    fn automaton(&self) -> &Automaton {
+        self.automaton.get_or_init(|| {
+            let mut seen = HashSet::new();
+            let mut literals = Vec::new();
+
+            for value in &self.values {
+                let encoded = self.encoders
+                    .iter()
+                    .map(|encoder| encoder(value));
+
+                for text in std::iter::once(value.clone()).chain(encoded) {
+                    if text.is_empty() || text.chars().count() < self.min_len || !seen.insert(text.clone()) {
+                        continue;
+                    }
+
+                    let text: Arc<str> = text.into();
+                    let utf16: Vec<u8> = text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+
+                    literals.push(Literal { bytes: text.as_bytes().to_vec(), text: text.clone(), utf16: false });
+                    literals.push(Literal { bytes: utf16, text, utf16: true });
+                }
+            }
+
+            Automaton::new(literals)
+        })
+    }
+
+    /*
+     * Finds every literal in data, ordered by start. The text of a match is
+     * the literal, also when found as UTF16. The index of a match is that of
+     * the literal, encodings included, until values are added.
+     */
+    pub fn find_matches(
+        &self,
+        data: &[u8],
+        want_text: bool) -> Vec<ScanMatch> {
+        let mut matches = Vec::new();
+
+        self.automaton().find(data, |start, literal, index| {
+            matches.push(
+                ScanMatch::from_literal(
+                    SECRET_LITERAL_RULE_ID,
+                    index,
+                    start as u64,
+                    literal.bytes.len() as u64,
+                    match want_text {
+                        true => { Some(literal.text.to_string()) },
+                        false => { None },
+                    },
+                    literal.utf16));
+        });
+
+        matches.sort_by_key(|m| m.start());
+
+        matches
+    }
+
+    /* Redacts every literal in input */
+    pub fn mask(
+        &self,
+        input: &str) -> String {
+        let matches = self.find_matches(input.as_bytes(), false);
+
+        redaction::redact(input, &matches, DEFAULT_LITERAL_REDACTION_TOKEN)
+    }
+}
+
+impl Default for LiteralMasker {
+    /* Finds base64, URL, JSON and backslash escaped values too */
+    fn default() -> Self {
+        Self::empty()
+            .with_encoder(base64_encode)
+            .with_encoder(uri_data_escape)
+            .with_encoder(json_escape)
+            .with_encoder(unescape_backslashes)
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use super::identifiable_scans::{ScanEngine, ScanMatch, ScanState};
+use super::redaction::{self, RedactionTemplate, DEFAULT_REDACTION_TOKEN};
+
+thread_local! {
+    /* Reused by every scrubber on the thread, so scans don't allocate */
+    static SCAN_STATE: RefCell<ScanState> = RefCell::new(ScanState::default());
+}
+
+/*
+ * Masks secrets in log text with a shared ScanEngine. Each thread scans
+ * with its own ScanState, so text without secrets costs a scan and nothing
+ * else. Used by SecretMaskingLogger and MaskingMakeWriter.
+ */
+#[derive(Clone)]
+pub struct LogScrubber {
+    engine: Arc<ScanEngine>,
+    template: Arc<RedactionTemplate>,
+}
+
+impl LogScrubber {
+    /* Redacts with DEFAULT_REDACTION_TOKEN */
+    pub fn new(engine: Arc<ScanEngine>) -> Self {
+        Self {
+            engine,
+            template: Arc::new(DEFAULT_REDACTION_TOKEN.parse().expect("Token has no placeholders")),
+        }
+    }
+
+    pub fn with_template(
+        self,
+        template: RedactionTemplate) -> Self {
+        let mut clone = self;
+
+        clone.template = Arc::new(template);
+
+        clone
+    }
+
+    pub fn engine(&self) -> &ScanEngine { &self.engine }
+
+    pub fn template(&self) -> &RedactionTemplate { &self.template }
+
+    /* Same as ScanEngine::find_matches(), with the thread's ScanState */
+    pub(crate) fn find_matches(
+        &self,
+        text: &str) -> Vec<ScanMatch> {
+        SCAN_STATE.with(|state| match state.try_borrow_mut() {
+            Ok(mut state) => { self.engine.find_matches_with_state(&mut state, text.as_bytes(), false) },
+            /* Only when a validator logs while the thread is scanning */
+            Err(_) => { self.engine.find_matches(text.as_bytes(), false) },
+        })
+    }
+
+    /* text with secrets redacted, borrowed when there are none */
+    pub fn scrub<'a>(
+        &self,
+        text: &'a str) -> Cow<'a, str> {
+        let matches = self.find_matches(text);
+
+        match matches.is_empty() {
+            true => { Cow::Borrowed(text) },
+            false => { Cow::Owned(redaction::redact_with_template(text, &matches, &self.template)) },
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+pub use self::log_support::SecretMaskingLogger;
+
+#[cfg(feature = "log")]
+mod log_support {
+    use std::borrow::Cow;
+    use std::fmt;
+
+    use log::kv::{self, Key, Source, Value, VisitSource};
+    use log::{Log, Metadata, Record};
+
+    use super::LogScrubber;
+
+    struct CollectPairs<'kvs>(Vec<(Key<'kvs>, Value<'kvs>)>);
+
+    impl<'kvs> VisitSource<'kvs> for CollectPairs<'kvs> {
+        fn visit_pair(
+            &mut self,
+            key: Key<'kvs>,
+            value: Value<'kvs>) -> Result<(), kv::Error> {
+            self.0.push((key, value));
+
+            Ok(())
+        }
+    }
+
+    /* Numbers and booleans can't be secrets */
+    fn is_primitive(value: &Value<'_>) -> bool {
+        value.to_u64().is_some() || value.to_i64().is_some() || value.to_f64().is_some() || value.to_bool().is_some()
+    }
+
+    /*
+     * A log::Log that masks secrets in the message and key values of each
+     * record before passing it to the logger it wraps.
+     */
+    pub struct SecretMaskingLogger<L> {
+        inner: L,
+        scrubber: LogScrubber,
+    }
+
+    impl<L: Log> SecretMaskingLogger<L> {
+        pub fn new(
+            inner: L,
+            scrubber: LogScrubber) -> Self {
+            Self {
+                inner,
+                scrubber,
+            }
+        }
+
+        pub fn inner(&self) -> &L { &self.inner }
+
+        fn log_masked(
+            &self,
+            record: &Record<'_>,
+            args: fmt::Arguments<'_>,
+            key_values: &dyn Source) {
+            let mut builder = Record::builder();
+
+            builder
+                .args(args)
+                .metadata(record.metadata().clone())
+                .line(record.line())
+                .key_values(key_values);
+
+            match record.module_path_static() {
+                Some(module_path) => { builder.module_path_static(Some(module_path)); },
+                None => { builder.module_path(record.module_path()); },
+            }
+
+            match record.file_static() {
+                Some(file) => { builder.file_static(Some(file)); },
+                None => { builder.file(record.file()); },
+            }
+
+            self.inner.log(&builder.build());
+        }
+    }
+
+    impl<L: Log> Log for SecretMaskingLogger<L> {
+        fn enabled(
+            &self,
+            metadata: &Metadata<'_>) -> bool {
+            self.inner.enabled(metadata)
+        }
+
+        fn log(
+            &self,
+            record: &Record<'_>) {
+            /* Disabled records aren't formatted or scanned */
+            if !self.inner.enabled(record.metadata()) {
+                return;
+            }
+
+            let message = match record.args().as_str() {
+                Some(message) => { Cow::Borrowed(message) },
+                None => { Cow::Owned(record.args().to_string()) },
+            };
+
+            let masked = self.scrubber.scrub(&message);
+
+            let mut pairs = CollectPairs(Vec::new());
+            let _ = record.key_values().visit(&mut pairs);
+
+            let masked_values: Vec<Option<String>> = pairs.0
+                .iter()
+                .map(|(_, value)| {
+                    if is_primitive(value) {
+                        return None;
+                    }
+
+                    let text = match value.to_borrowed_str() {
+                        Some(text) => { Cow::Borrowed(text) },
+                        None => { Cow::Owned(value.to_string()) },
+                    };
+
+                    match self.scrubber.scrub(&text) {
+                        Cow::Owned(masked) => { Some(masked) },
+                        Cow::Borrowed(_) => { None },
+                    }
+                })
+                .collect();
+
+            if matches!(masked, Cow::Borrowed(_)) && masked_values.iter().all(Option::is_none) {
+                self.inner.log(record);
+                return;
+            }
+
+            let key_values: Vec<(Key<'_>, Value<'_>)> = pairs.0
+                .iter()
+                .zip(&masked_values)
+                .map(|((key, value), masked)| match masked {
+                    Some(masked) => { (key.clone(), Value::from(masked.as_str())) },
+                    None => { (key.clone(), value.clone()) },
+                })
+                .collect();
+
+            self.log_masked(record, format_args!("{}", masked), &key_values.as_slice());
+        }
+
+        fn flush(&self) {
+            self.inner.flush();
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+pub use self::tracing_support::{layer, MaskingIoWriter, MaskingLayer, MaskingMakeWriter};
+
+#[cfg(feature = "tracing")]
+mod tracing_support {
+    use std::io;
+
+    use tracing_subscriber::fmt::format::{DefaultFields, Format};
+    use tracing_subscriber::fmt::{Layer, MakeWriter};
+
+    use super::LogScrubber;
+
+    /*
+     * A tracing-subscriber MakeWriter whose writers mask secrets in what
+     * they write. fmt layers write each formatted event at once, so every
+     * event format is masked, json() and pretty() included.
+     */
+    #[derive(Clone)]
+    pub struct MaskingMakeWriter<M> {
+        make_writer: M,
+        scrubber: LogScrubber,
+    }
+
+    impl<M> MaskingMakeWriter<M> {
+        pub fn new(
+            make_writer: M,
+            scrubber: LogScrubber) -> Self {
+            Self {
+                make_writer,
+                scrubber,
+            }
+        }
+    }
+
+    impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for MaskingMakeWriter<M> {
+        type Writer = MaskingIoWriter<'a, M::Writer>;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            MaskingIoWriter::new(self.make_writer.make_writer(), &self.scrubber)
+        }
+
+        fn make_writer_for(
+            &'a self,
+            meta: &tracing_core::Metadata<'_>) -> Self::Writer {
+            MaskingIoWriter::new(self.make_writer.make_writer_for(meta), &self.scrubber)
+        }
+    }
+
+    /*
+     * Holds back what is written until it is flushed or dropped, then
+     * writes it with secrets masked. Secrets split across writes are
+     * masked too.
+     */
+    pub struct MaskingIoWriter<'a, W: io::Write> {
+        inner: W,
+        scrubber: &'a LogScrubber,
+        buffer: Vec<u8>,
+    }
+
+    impl<'a, W: io::Write> MaskingIoWriter<'a, W> {
+        pub fn new(
+            inner: W,
+            scrubber: &'a LogScrubber) -> Self {
+            Self {
+                inner,
+                scrubber,
+                buffer: Vec::new(),
+            }
+        }
+
+        fn write_masked(&mut self) -> io::Result<()> {
+            if self.buffer.is_empty() {
+                return Ok(());
+            }
+
+            /* Formatted records are UTF8 */
+            let text = String::from_utf8_lossy(&self.buffer);
+            let result = self.inner.write_all(self.scrubber.scrub(&text).as_bytes());
+
+            self.buffer.clear();
+
+            result
+        }
+    }
+
+    impl<W: io::Write> io::Write for MaskingIoWriter<'_, W> {
+        fn write(
+            &mut self,
+            buf: &[u8]) -> io::Result<usize> {
+            self.buffer.extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.write_masked()?;
+            self.inner.flush()
+        }
+    }
+
+    impl<W: io::Write> Drop for MaskingIoWriter<'_, W> {
+        fn drop(&mut self) {
+            let _ = self.write_masked();
+        }
+    }
+
+    pub type MaskingLayer<S> = Layer<S, DefaultFields, Format, MaskingMakeWriter<fn() -> io::Stdout>>;
+
+    /*
+     * A tracing-subscriber fmt layer writing to stdout that masks secrets
+     * in every event it writes. Its format can be changed, with json() and
+     * the like, but with_writer() replaces the masking writer, pass a
+     * MaskingMakeWriter to it instead.
+     */
+    pub fn layer<S>(scrubber: LogScrubber) -> MaskingLayer<S> {
+        tracing_subscriber::fmt::layer().with_writer(MaskingMakeWriter::new(io::stdout as fn() -> io::Stdout, scrubber))
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::data_classification;
+use super::identifiable_scans::{case_variants, Confidence, ScanDefinition, Validation};
+
+pub const LOOSE_SAS_SECRET_RULE_ID: &str = "SEC101/060";
+pub const OAUTH2_BEARER_TOKEN_RULE_ID: &str = "SEC101/061";
+
+/*
+ * Port of the .NET LooseSasSecret rule:
+ * (?i)(?:^|[?;&])(?:dsas_secret|sig)=(?P<refine>[0-9a-z\/+%]{43,129}(?:=|%3d))
+ *
+ * Parameters are matched in any case. Unlike .NET, percent-encoded values
+ * are decoded, so the value must be an HMAC-SHA256, 43 base64 characters
+ * and padding, however they are encoded. Separators can be encoded too.
+ */
+const SAS_PARAMETERS: [&[u8]; 2] = [b"sig", b"dsas_secret"];
+
+/* Longest parameter before its signature plus an encoded separator */
+const SAS_LOOKBEHIND: usize = 12;
+
+/* Base64 characters of an HMAC-SHA256 without padding */
+const SAS_SECRET_LEN: usize = 43;
+
+/* Signature, an encoded '=' and every character encoded */
+const SAS_MAX_LEN: usize = 5 + (SAS_SECRET_LEN + 1) * 3;
+
+/*
+ * Port of the .NET OAuth2BearerToken rule:
+ * (?i)authorization:(\s|%20)bearer(\s|%20)(?P<refine>[0-9a-z][UrlUnreserved+\/=]*)
+ *
+ * The signature is the "zat" of the header, z being far rarer than the
+ * ':' after it, and the rest of the header is checked by the validator.
+ */
+const BEARER_HEADER: &[u8] = b"authorization:";
+
+const BEARER_SIGNATURE: &[u8] = b"zat";
+
+/* Where the signature is in the header */
+const BEARER_SIGNATURE_OFFSET: usize = 7;
+
+const BEARER_LOOKBEHIND: usize = BEARER_SIGNATURE_OFFSET;
+
+/* Same as JWTs, which are what most bearer tokens are */
+const BEARER_MAX_LEN: usize = 8 * 1024;
+
+fn ends_with_ignore_case(
+    data: &[u8],
+    suffix: &[u8]) -> bool {
+    data.len() >= suffix.len() && data[data.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+}
+
+fn starts_with_ignore_case(
+    data: &[u8],
+    prefix: &[u8]) -> bool {
+    data.len() >= prefix.len() && data[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+/* Value of a %XX escape at the start of data */
+fn percent_decode(data: &[u8]) -> Option<u8> {
+    if data.len() < 3 || data[0] != b'%' || !data[1..3].iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    let hex = std::str::from_utf8(&data[1..3]).ok()?;
+
+    u8::from_str_radix(hex, 16).ok()
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+fn validate_sas(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    /* The signature is the end of the parameter name and '=' or '%' */
+    let parameter = SAS_PARAMETERS
+        .iter()
+        .find(|parameter| ends_with_ignore_case(&data[..anchor + 2], parameter))?;
+
+    let parameter_start = anchor + 2 - parameter.len();
+    let before = &data[..parameter_start];
+
+    let separated =
+        before.is_empty() ||
+        matches!(before[before.len() - 1], b'?' | b';' | b'&') ||
+        [b"%26", b"%3f", b"%3b"].iter().any(|encoded| ends_with_ignore_case(before, *encoded));
+
+    if !separated {
+        return None;
+    }
+
+    let value_start = match data[anchor + 2] {
+        b'=' => { anchor + 3 },
+        _ if percent_decode(&data[anchor + 2..]) == Some(b'=') => { anchor + 5 },
+        _ => { return None; },
+    };
+
+    /* Count decoded base64 characters up to the padding */
+    let mut pos = value_start;
+    let mut decoded = 0;
+
+    let end = loop {
+        let b = *data.get(pos)?;
+
+        let (value, len) = match b {
+            b'%' => { (percent_decode(&data[pos..])?, 3) },
+            _ => { (b, 1) },
+        };
+
+        match value {
+            b'=' => { break pos + len; },
+            _ if is_base64_char(value) && decoded < SAS_SECRET_LEN => { decoded += 1; },
+            _ => { return None; },
+        }
+
+        pos += len;
+    };
+
+    if decoded != SAS_SECRET_LEN {
+        return None;
+    }
+
+    let parameter = std::str::from_utf8(parameter).ok()?;
+
+    Some(
+        Validation::new(value_start, end - value_start)
+        .with_context("parameter", parameter))
+}
+
+/* A single whitespace or %20, returns its length */
+fn separator_len(data: &[u8]) -> Option<usize> {
+    match data.first()? {
+        b if b.is_ascii_whitespace() => { Some(1) },
+        b'%' if data.get(1..3) == Some(b"20") => { Some(3) },
+        _ => { None },
+    }
+}
+
+fn is_bearer_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'~' | b'.' | b'-' | b'+' | b'/' | b'=')
+}
+
+fn validate_bearer(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    let header_start = anchor.checked_sub(BEARER_SIGNATURE_OFFSET)?;
+
+    if !starts_with_ignore_case(&data[header_start..], BEARER_HEADER) {
+        return None;
+    }
+
+    let mut pos = header_start + BEARER_HEADER.len();
+    pos += separator_len(&data[pos..])?;
+
+    if !starts_with_ignore_case(&data[pos..], b"bearer") {
+        return None;
+    }
+
+    pos += 6;
+    pos += separator_len(&data[pos..])?;
+
+    let start = pos;
+
+    if !data.get(start)?.is_ascii_alphanumeric() {
+        return None;
+    }
+
+    let len = data[start..]
+        .iter()
+        .take_while(|b| is_bearer_token_char(**b))
+        .count();
+
+    /* Possibly longer than what was fetched */
+    if start + len - anchor >= BEARER_MAX_LEN {
+        return None;
+    }
+
+    /* GUIDs and numbers are ids or placeholders, not tokens */
+    let token = std::str::from_utf8(&data[start..start + len]).ok()?;
+
+    if data_classification::classify(token).is_some() {
+        return None;
+    }
+
+    Some(Validation::new(start, len))
+}
+
+/*
+ * Both rules match in any case, so there is a definition for each case
+ * of the signature. These are medium confidence.
+ */
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    let mut defs = Vec::new();
+
+    /*
+     * The sig chars are the '=' or '%' after the parameter. Every letter of
+     * sig and dsas_secret, in either case, is in more of the text scanned
+     * than '=' is, so anchoring on one would skip less of it.
+     */
+    for (sig, sig_char) in [(b"ig=", b'='), (b"ig%", b'%'), (b"et=", b'='), (b"et%", b'%')] {
+        for sig in case_variants(sig) {
+            defs.push(
+                ScanDefinition::with_context(
+                    LOOSE_SAS_SECRET_RULE_ID,
+                    &sig,
+                    sig_char,
+                    3,
+                    SAS_MAX_LEN,
+                    SAS_LOOKBEHIND,
+                    validate_sas)
+                .with_confidence(Confidence::Medium));
+        }
+    }
+
+    for sig in case_variants(BEARER_SIGNATURE) {
+        defs.push(
+            ScanDefinition::with_context(
+                OAUTH2_BEARER_TOKEN_RULE_ID,
+                &sig,
+                sig[0],
+                BEARER_SIGNATURE.len() as u64,
+                BEARER_MAX_LEN,
+                BEARER_LOOKBEHIND,
+                validate_bearer)
+            .with_confidence(Confidence::Medium));
+    }
+
+    defs
+}
@@ -1,13 +1,13 @@
 // Copyright (c) Microsoft. All rights reserved.
 // Licensed under the MIT license. See LICENSE file in the project root for full license information.
 
-/// This is a Rust implementation of the Marvin32 checksum algorithm, the definitive native code for which is
-/// at https://github.com/microsoft/SymCrypt/blob/master/lib/marvin32.c.
+//! This is a Rust implementation of the Marvin32 checksum algorithm, the definitive native code for which is
+//! at https://github.com/microsoft/SymCrypt/blob/master/lib/marvin32.c.
 
 /// Convenience method to compute a Marvin hash and collapse it into a 32-bit hash.
 pub fn compute_hash32(data: &[u8], seed: u64, offset: i32, length: i32) -> i32 {
     let hash64 = compute_hash(data, seed, offset, length);
-    return ((hash64 >> 32) as i32) ^ (hash64 as i32);
+    ((hash64 >> 32) as i32) ^ (hash64 as i32)
 }
 
 /// Computes a 64-bit hash using the Marvin algorithm from the given slice,
@@ -35,7 +35,7 @@ pub fn compute_hash(data: &[u8], seed: u64, offset: i32, length: i32) -> i64 {
 /// Convenience method to compute a Marvin hash from a slice and collapse it into a 32-bit hash.
 pub fn compute_hash32_slice(data: &[u8], seed: u64) -> i32 {
     let hash64 = compute_hash_slice(data, seed);
-    return ((hash64 >> 32) as i32) ^ (hash64 as i32);
+    ((hash64 >> 32) as i32) ^ (hash64 as i32)
 }
 
 /// Computes a 64-bit hash using the Marvin algorithm from a slice.
@@ -75,20 +75,20 @@ pub fn compute_hash_slice(data: &[u8], seed: u64) -> i64 {
     let (p0, p1) = block(p0, p1);
     let (p0, p1) = block(p0, p1);
 
-    return ((p1 as i64) << 32) | (p0 as i64);
+    ((p1 as i64) << 32) | (p0 as i64)
 }
 
 /// Combines hash code of multiple objects while trying to minimize possibility of collisions.
 /// rp0: hash code seed.
 /// rp1: Delegates to generate hash codes to combine.
 fn block(mut p0: u32, mut p1: u32) -> (u32, u32) {
-    p1 = p1 ^ p0;
+    p1 ^= p0;
     p0 = p0.rotate_left(20);
 
     p0 = p0.wrapping_add(p1);
     p1 = p1.rotate_left(9);
 
-    p1 = p1 ^ p0;
+    p1 ^= p0;
     p0 = p0.rotate_left(27);
 
     p0 = p0.wrapping_add(p1);
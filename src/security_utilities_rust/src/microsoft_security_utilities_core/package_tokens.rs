@@ -0,0 +1,142 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::identifiable_scans::{ScanDefinition, Validation};
+use super::validators::CharClass;
+
+pub const NUGET_API_KEY_RULE_ID: &str = "SEC101/031";
+pub const NPM_AUTHOR_KEY_RULE_ID: &str = "SEC101/050";
+pub const AZURE_DATABRICKS_PAT_RULE_ID: &str = "SEC101/110";
+
+/*
+ * Ports of the .NET NuGetApiKey, NpmAuthorKey and AzureDatabricksPat rules.
+ * The compiled high-performance patterns only check the characters after
+ * the signature, these also check what surrounds the token like the .NET
+ * regexes do, e.g. PrefixBase62 and SuffixBase62 for npm:
+ * (^|[^0-9A-Za-z])npm_[0-9A-Za-z]{36}([^0-9A-Za-z]|$)
+ */
+struct PackageToken {
+    id: &'static str,
+    signature: &'static [u8],
+    sig_char: u8,
+    body: CharClass,
+    min_body_len: usize,
+    max_body_len: usize,
+    boundary: CharClass,
+}
+
+fn base32() -> CharClass {
+    CharClass::alphabetic().union(CharClass::range(b'2', b'7'))
+}
+
+fn hex_or_dash() -> CharClass {
+    CharClass::range(b'0', b'9')
+        .union(CharClass::range(b'a', b'f'))
+        .union(CharClass::byte(b'-'))
+}
+
+fn tokens() -> [PackageToken; 4] {
+    [
+        /* (?i)(^|[^a-z0-9])oy2[a-z2-7]{43}([^a-z0-9]|$) */
+        PackageToken {
+            id: NUGET_API_KEY_RULE_ID,
+            signature: b"oy2",
+            sig_char: b'2',
+            body: base32(),
+            min_body_len: 43,
+            max_body_len: 43,
+            boundary: CharClass::alphanumeric(),
+        },
+        PackageToken {
+            id: NUGET_API_KEY_RULE_ID,
+            signature: b"OY2",
+            sig_char: b'2',
+            body: base32(),
+            min_body_len: 43,
+            max_body_len: 43,
+            boundary: CharClass::alphanumeric(),
+        },
+        PackageToken {
+            id: NPM_AUTHOR_KEY_RULE_ID,
+            signature: b"npm_",
+            sig_char: b'_',
+            body: CharClass::alphanumeric(),
+            min_body_len: 36,
+            max_body_len: 36,
+            boundary: CharClass::alphanumeric(),
+        },
+        /* (^|[^0-9a-f\-])dapi[0-9a-f\-]{32,34}([^0-9a-f\-]|$) */
+        PackageToken {
+            id: AZURE_DATABRICKS_PAT_RULE_ID,
+            signature: b"dapi",
+            sig_char: b'p',
+            body: hex_or_dash(),
+            min_body_len: 32,
+            max_body_len: 34,
+            boundary: hex_or_dash(),
+        },
+    ]
+}
+
+fn validate(
+    token: &PackageToken,
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    if anchor > 0 && token.boundary.contains(data[anchor - 1]) {
+        return None;
+    }
+
+    let body_start = anchor + token.signature.len();
+
+    let body_len = data[body_start..]
+        .iter()
+        .take(token.max_body_len)
+        .take_while(|b| token.body.contains(**b))
+        .count();
+
+    if body_len < token.min_body_len {
+        return None;
+    }
+
+    let end = body_start + body_len;
+
+    if end < data.len() && token.boundary.contains(data[end]) {
+        return None;
+    }
+
+    /*
+     * NuGet accepts keys in either case, a mix of both is more likely to
+     * be base64 than a key.
+     */
+    if token.id == NUGET_API_KEY_RULE_ID {
+        let key = &data[anchor..end];
+
+        if key.iter().any(u8::is_ascii_lowercase) && key.iter().any(u8::is_ascii_uppercase) {
+            return None;
+        }
+    }
+
+    Some(Validation::new(anchor, end - anchor))
+}
+
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    tokens()
+        .into_iter()
+        .map(|token| {
+            let before = token.signature.len();
+            let min_len = before + token.min_body_len;
+            let len = before + token.max_body_len;
+
+            ScanDefinition::with_context(
+                token.id,
+                token.signature,
+                token.sig_char,
+                before as u64,
+                /* One more to check the boundary after the token */
+                len + 1,
+                1,
+                move |data, anchor| validate(&token, data, anchor))
+            .with_min_len(min_len)
+        })
+        .collect()
+}
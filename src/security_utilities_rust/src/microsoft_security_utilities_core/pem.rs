@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::identifiable_scans::{ScanDefinition, Validation};
+
+/*
+ * The .NET rules have no equivalent, so there is no SEC101 id for this
+ * rule. Matches report the key type as context.
+ */
+pub const PEM_PRIVATE_KEY_RULE_ID: &str = "PemPrivateKey";
+
+/* Armor labels and the key type they are reported as */
+const LABELS: [(&[u8], &str); 7] = [
+    (b"RSA PRIVATE KEY", "RSA"),
+    (b"EC PRIVATE KEY", "EC"),
+    (b"DSA PRIVATE KEY", "DSA"),
+    (b"OPENSSH PRIVATE KEY", "OpenSSH"),
+    (b"PRIVATE KEY", "PKCS#8"),
+    (b"ENCRYPTED PRIVATE KEY", "Encrypted PKCS#8"),
+    (b"PGP PRIVATE KEY BLOCK", "PGP"),
+];
+
+/* Part of every label, the signature char is the least common of them */
+const SIGNATURE: &[u8] = b"E KE";
+
+const BEGIN: &[u8] = b"-----BEGIN ";
+const END: &[u8] = b"-----END ";
+const DASHES: &[u8] = b"-----";
+
+/* From the start of the longest armor to the signature */
+const PEM_LOOKBEHIND: usize = 32;
+
+/* Covers 16384 bit RSA keys with room to spare */
+const PEM_MAX_LEN: usize = 64 * 1024;
+
+/* Fewer base64 characters than this are placeholders, not keys */
+const PEM_MIN_BODY_LEN: usize = 64;
+
+/*
+ * Keys are often in JSON or environment variables, where line breaks are
+ * escaped or replaced by spaces. Returns the length of a line break.
+ */
+fn line_break_len(data: &[u8]) -> usize {
+    match data {
+        [b'\r' | b'\n', ..] => { 1 },
+        [b'\\', b'r' | b'n', ..] => { 2 },
+        _ => { 0 },
+    }
+}
+
+fn is_blank(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+fn is_body_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'='
+}
+
+/*
+ * Checks the body between the armor lines is headers, such as Proc-Type
+ * for encrypted keys, followed by base64. Returns where the body starts
+ * and ends without surrounding line breaks and blanks.
+ */
+fn validate_body(body: &[u8]) -> Option<(usize, usize)> {
+    let mut pos = 0;
+    let mut body_start = None;
+    let mut body_end = 0;
+    let mut base64_len = 0;
+    let mut headers = true;
+
+    while pos < body.len() {
+        let skip = match is_blank(body[pos]) {
+            true => { 1 },
+            false => { line_break_len(&body[pos..]) },
+        };
+
+        if skip != 0 {
+            pos += skip;
+            continue;
+        }
+
+        let line_start = pos;
+
+        while pos < body.len() && line_break_len(&body[pos..]) == 0 {
+            pos += 1;
+        }
+
+        let line = body[line_start..pos].trim_ascii_end();
+
+        body_start.get_or_insert(line_start);
+        body_end = line_start + line.len();
+
+        if headers && line.contains(&b':') {
+            /* Printable ASCII like the base64 that follows */
+            match line.iter().all(|b| b.is_ascii_graphic() || is_blank(*b)) {
+                true => { continue; },
+                false => { return None; },
+            }
+        }
+
+        headers = false;
+
+        for b in line.iter().filter(|b| !is_blank(**b)) {
+            if !is_body_char(*b) {
+                return None;
+            }
+
+            base64_len += 1;
+        }
+    }
+
+    if base64_len < PEM_MIN_BODY_LEN {
+        return None;
+    }
+
+    Some((body_start?, body_end))
+}
+
+fn find(
+    data: &[u8],
+    needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(
+    data: &[u8],
+    needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).rposition(|window| window == needle)
+}
+
+fn validate(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    let begin = rfind(&data[..anchor], BEGIN)?;
+    let label_start = begin + BEGIN.len();
+    let label_end = anchor + find(&data[anchor..], DASHES)?;
+    let label = &data[label_start..label_end];
+
+    let (_, key_type) = LABELS
+        .iter()
+        .find(|(known, _)| *known == label)?;
+
+    let body_start = label_end + DASHES.len();
+
+    /* The END armor must match the BEGIN armor */
+    let mut end_armor = END.to_vec();
+    end_armor.extend_from_slice(label);
+    end_armor.extend_from_slice(DASHES);
+
+    let end = body_start + find(&data[body_start..], &end_armor)?;
+    let (start, stop) = validate_body(&data[body_start..end])?;
+
+    Some(
+        Validation::new(begin, end + end_armor.len() - begin)
+        .with_redact(body_start + start - begin, stop - start)
+        .with_context("key_type", *key_type))
+}
+
+/*
+ * The whole block from BEGIN to END armor is the match, only the body is
+ * redacted. Blocks can be up to 64 KiB, buffers for matches_reader() must
+ * be too.
+ */
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    vec![
+        ScanDefinition::with_context(
+            PEM_PRIVATE_KEY_RULE_ID,
+            SIGNATURE,
+            b'K',
+            SIGNATURE.len() as u64,
+            PEM_MAX_LEN,
+            PEM_LOOKBEHIND,
+            validate),
+    ]
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use super::identifiable_scans::{Confidence, ScanDefinition, Validation};
+
+pub const PKCS12_RULE_ID: &str = "SEC101/055";
+
+/*
+ * Port of the .NET Pkcs12CertificatePrivateKeyBundle rule, which matches
+ * the start of a base64 PFX blob: MI[I-L][0-9a-zA-Z\/+]{2}[AQgw]IBAzCC
+ *
+ * That is a SEQUENCE with a two byte length, the version 3 INTEGER and the
+ * start of the authSafe ContentInfo SEQUENCE. Unlike .NET, the ContentInfo
+ * content type is decoded and checked too, and the match is the whole
+ * base64 blob so redaction removes all of it, across line breaks too.
+ */
+const SIGNATURE: &[u8] = b"IBAz";
+
+/* Characters from the start of the blob to the end of the signature */
+const SIGNATURE_END: u64 = 10;
+
+/* Enough base64 to decode up to the ContentInfo content type */
+const PKCS12_MIN_LEN: usize = 32;
+
+/* Base64 of a blob with the largest two byte length */
+const PKCS12_MAX_BASE64_LEN: usize = (4 + 0xFFFF_usize).div_ceil(3) * 4;
+
+/* That blob wrapped at 64 columns with escaped CRLF line breaks */
+const PKCS12_MAX_LEN: usize = PKCS12_MAX_BASE64_LEN + PKCS12_MAX_BASE64_LEN / 64 * 4;
+
+const PKCS7_OID_PREFIX: [u8; 8] = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07];
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+/* Length of the line break at the start of data, also escaped as in JSON or code */
+fn line_break_len(data: &[u8]) -> usize {
+    match data {
+        [b'\r', b'\n', ..] => { 2 },
+        [b'\r' | b'\n', ..] => { 1 },
+        [b'\\', b'r', b'\\', b'n', ..] => { 4 },
+        [b'\\', b'r' | b'n', ..] => { 2 },
+        _ => { 0 },
+    }
+}
+
+/*
+ * Up to max characters of the base64 at the start of data, padding
+ * included, and where the last of them ends. A line break between them is
+ * skipped, tools such as base64 and certutil wrap at 64 or 76 columns.
+ */
+fn base64_chars(
+    data: &[u8],
+    max: usize) -> (Vec<u8>, usize) {
+    let mut chars = Vec::new();
+    let mut pos = 0;
+    let mut end = 0;
+
+    while chars.len() < max {
+        let b = match data.get(pos) {
+            Some(b) => { *b },
+            None => { break; },
+        };
+
+        let padding = chars.iter().rev().take_while(|c| **c == b'=').count();
+
+        if (padding == 0 && is_base64_char(b)) || (b == b'=' && padding < 2) {
+            chars.push(b);
+            pos += 1;
+            end = pos;
+            continue;
+        }
+
+        /* A blank line ends the blob, as it does in PEM */
+        match line_break_len(&data[pos..]) {
+            0 => { break; },
+            _ if pos != end => { break; },
+            len => { pos += len; },
+        }
+    }
+
+    (chars, end)
+}
+
+fn validate(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    let data = &data[anchor..];
+    let (chars, _) = base64_chars(data, PKCS12_MIN_LEN);
+
+    if chars.len() < PKCS12_MIN_LEN {
+        return None;
+    }
+
+    let der = STANDARD.decode(&chars).ok()?;
+
+    /* PFX SEQUENCE and version 3 */
+    if der[0..2] != [0x30, 0x82] || der[4..7] != [0x02, 0x01, 0x03] {
+        return None;
+    }
+
+    /* authSafe ContentInfo SEQUENCE and its contentType OID */
+    if der[7..9] != [0x30, 0x82] || der[11..13] != [0x06, 0x09] || der[13..21] != PKCS7_OID_PREFIX {
+        return None;
+    }
+
+    let content_type = match der[21] {
+        0x01 => { "data" },
+        0x02 => { "signedData" },
+        _ => { return None; },
+    };
+
+    /*
+     * The blob ends where the PFX SEQUENCE does. Truncated blobs are still
+     * reported, up to where the base64 ends.
+     */
+    let der_len = 4 + u16::from_be_bytes([der[2], der[3]]) as usize;
+    let (_, len) = base64_chars(data, der_len.div_ceil(3) * 4);
+
+    Some(
+        Validation::new(anchor, len)
+        .with_context("content_type", content_type)
+        .with_context("der_len", der_len.to_string()))
+}
+
+/*
+ * PFX blobs can be up to 85 KiB of base64, more with line breaks, buffers
+ * for matches_reader() must be too. These are medium confidence, like .NET.
+ */
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    vec![
+        ScanDefinition::with_context(
+            PKCS12_RULE_ID,
+            SIGNATURE,
+            b'z',
+            SIGNATURE_END,
+            PKCS12_MAX_LEN,
+            0,
+            validate)
+        .with_min_len(PKCS12_MIN_LEN)
+        .with_confidence(Confidence::Medium),
+    ]
+}
@@ -0,0 +1,523 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fmt::{self, Write};
+use std::sync::{Arc, OnceLock};
+
+use super::identifiable_scans::{ScanEngine, ScanMatch, ScanOptions};
+use super::log_redaction::LogScrubber;
+use super::redaction;
+
+/*
+ * MaskingWriter scans this much at a time, besides what it keeps of the
+ * previous scan so secrets split across writes are found.
+ */
+const WINDOW_LEN: usize = 8 * 1024;
+
+static GLOBAL_SCRUBBER: OnceLock<LogScrubber> = OnceLock::new();
+
+/*
+ * Sets the scrubber Redacted masks with. Fails, returning scrubber, once
+ * the global scrubber is set or has been used.
+ */
+pub fn set_global_scrubber(scrubber: LogScrubber) -> Result<(), LogScrubber> {
+    GLOBAL_SCRUBBER.set(scrubber)
+}
+
+/* The scrubber Redacted masks with, ScanOptions::default() unless set */
+pub fn global_scrubber() -> &'static LogScrubber {
+    GLOBAL_SCRUBBER.get_or_init(|| LogScrubber::new(Arc::new(ScanEngine::new(ScanOptions::default()))))
+}
+
+/*
+ * A fmt::Write that masks secrets in what is written to it before writing
+ * it to inner. Text is held back, a window and the longest match window of
+ * the engine, until a later write or finish(), which must be called to
+ * write the rest. Secrets that run past that are held back until whole.
+ */
+pub struct MaskingWriter<'a, W: fmt::Write> {
+    inner: W,
+    scrubber: &'a LogScrubber,
+    buffer: String,
+    /* Kept for the next scan, the longest window the engine validates */
+    overlap: usize,
+    /* Scans when the buffer is this long, a window past what was kept */
+    flush_len: usize,
+}
+
+impl<'a, W: fmt::Write> MaskingWriter<'a, W> {
+    pub fn new(
+        inner: W,
+        scrubber: &'a LogScrubber) -> Self {
+        Self {
+            inner,
+            scrubber,
+            buffer: String::new(),
+            overlap: scrubber.engine().max_window_len(),
+            flush_len: WINDOW_LEN + scrubber.engine().max_window_len(),
+        }
+    }
+
+    /* Where the definition of scan_match starts validating, its context included */
+    fn window_start(
+        &self,
+        scan_match: &ScanMatch) -> usize {
+        let lookbehind = self.scrubber
+            .engine()
+            .scan_defs()
+            .get(scan_match.def_index() as usize)
+            .map_or(0, |def| def.lookbehind());
+
+        let lookbehind = match scan_match.is_utf16() {
+            true => { lookbehind * 2 },
+            false => { lookbehind },
+        };
+
+        (scan_match.start() as usize).saturating_sub(lookbehind)
+    }
+
+    /*
+     * Writes out the buffer, all of it when this is the last of it. Else
+     * the overlap is kept, and so is every secret that can't be written
+     * whole from where its definition starts validating, so the next scan
+     * finds it again with its context.
+     */
+    fn flush_window(
+        &mut self,
+        last: bool) -> fmt::Result {
+        let matches = self.scrubber.find_matches(&self.buffer);
+        let redactions = redaction::template_redactions(self.buffer.as_bytes(), &matches, false, self.scrubber.template());
+
+        let mut flush_to = match last {
+            true => { self.buffer.len() },
+            false => { self.buffer.len().saturating_sub(self.overlap) },
+        };
+
+        /* Neither secrets nor redactions are split */
+        loop {
+            let held = matches
+                .iter()
+                .filter(|m| (m.redact_start() + m.redact_len()) as usize > flush_to)
+                .map(|m| self.window_start(m))
+                .chain(redactions.iter().filter(|(start, end, _)| *start < flush_to && *end > flush_to).map(|(start, _, _)| *start))
+                .filter(|start| *start < flush_to)
+                .min();
+
+            match held {
+                Some(start) => { flush_to = start; },
+                None if !self.buffer.is_char_boundary(flush_to) => { flush_to -= 1; },
+                None => { break; },
+            }
+        }
+
+        let written: Vec<_> = redactions
+            .into_iter()
+            .filter(|(_, end, _)| *end <= flush_to)
+            .collect();
+
+        let output = redaction::apply_redactions(&self.buffer.as_bytes()[..flush_to], &written);
+
+        self.inner.write_str(&String::from_utf8_lossy(&output))?;
+        self.buffer.drain(..flush_to);
+        self.flush_len = (WINDOW_LEN + self.overlap).max(self.buffer.len() + WINDOW_LEN);
+
+        Ok(())
+    }
+
+    /* Writes what is held back, returning inner */
+    pub fn finish(mut self) -> Result<W, fmt::Error> {
+        self.flush_window(true)?;
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for MaskingWriter<'_, W> {
+    fn write_str(
+        &mut self,
+        text: &str) -> fmt::Result {
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let mut len = (self.flush_len - self.buffer.len()).min(rest.len());
+
+            while !rest.is_char_boundary(len) {
+                len += 1;
+            }
+
+            self.buffer.push_str(&rest[..len]);
+            rest = &rest[len..];
+
+            if self.buffer.len() >= self.flush_len {
+                self.flush_window(false)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/*
+ * Wraps a value so its Debug and Display output, and with the serde
+ * feature what it serializes, has secrets masked by the global scrubber.
+ * Wrap config structs and errors that might hold keys.
+ */
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Redacted<T>(pub T);
+
+impl<T> Redacted<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn get(&self) -> &T { &self.0 }
+
+    pub fn into_inner(self) -> T { self.0 }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternate = f.alternate();
+        let mut writer = MaskingWriter::new(f, global_scrubber());
+
+        match alternate {
+            true => { write!(writer, "{:#?}", self.0)?; },
+            false => { write!(writer, "{:?}", self.0)?; },
+        }
+
+        writer.finish().map(|_| ())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = MaskingWriter::new(f, global_scrubber());
+
+        write!(writer, "{}", self.0)?;
+
+        writer.finish().map(|_| ())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_support {
+    use serde::ser::{self, Serialize, Serializer};
+
+    use super::super::log_redaction::LogScrubber;
+    use super::super::redaction;
+    use super::{global_scrubber, Redacted};
+
+    impl<T: Serialize> Serialize for Redacted<T> {
+        fn serialize<S: Serializer>(
+            &self,
+            serializer: S) -> Result<S::Ok, S::Error> {
+            Masked(&self.0, global_scrubber()).serialize(serializer)
+        }
+    }
+
+    /* A value serialized with secrets masked in its strings and bytes */
+    struct Masked<'a, T: ?Sized>(&'a T, &'a LogScrubber);
+
+    impl<T: ?Sized + Serialize> Serialize for Masked<'_, T> {
+        fn serialize<S: Serializer>(
+            &self,
+            serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(MaskingSerializer {
+                inner: serializer,
+                scrubber: self.1,
+            })
+        }
+    }
+
+    struct MaskingSerializer<'a, S> {
+        inner: S,
+        scrubber: &'a LogScrubber,
+    }
+
+    /* A sequence, tuple, map or struct, whose members are masked */
+    struct Compound<'a, C> {
+        inner: C,
+        scrubber: &'a LogScrubber,
+    }
+
+    impl<'a, S: Serializer> MaskingSerializer<'a, S> {
+        fn compound<C>(
+            scrubber: &'a LogScrubber,
+            inner: Result<C, S::Error>) -> Result<Compound<'a, C>, S::Error> {
+            Ok(Compound {
+                inner: inner?,
+                scrubber,
+            })
+        }
+    }
+
+    impl<'a, S: Serializer> Serializer for MaskingSerializer<'a, S> {
+        type Ok = S::Ok;
+        type Error = S::Error;
+        type SerializeSeq = Compound<'a, S::SerializeSeq>;
+        type SerializeTuple = Compound<'a, S::SerializeTuple>;
+        type SerializeTupleStruct = Compound<'a, S::SerializeTupleStruct>;
+        type SerializeTupleVariant = Compound<'a, S::SerializeTupleVariant>;
+        type SerializeMap = Compound<'a, S::SerializeMap>;
+        type SerializeStruct = Compound<'a, S::SerializeStruct>;
+        type SerializeStructVariant = Compound<'a, S::SerializeStructVariant>;
+
+        fn serialize_str(
+            self,
+            v: &str) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_str(&self.scrubber.scrub(v))
+        }
+
+        fn serialize_bytes(
+            self,
+            v: &[u8]) -> Result<S::Ok, S::Error> {
+            let matches = self.scrubber.engine().find_matches(v, false);
+
+            match matches.is_empty() {
+                true => { self.inner.serialize_bytes(v) },
+                false => {
+                    let masked = redaction::redact_bytes_with_template(v, &matches, self.scrubber.template());
+
+                    self.inner.serialize_bytes(&masked)
+                },
+            }
+        }
+
+        fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> { self.inner.serialize_bool(v) }
+
+        fn serialize_i8(self, v: i8) -> Result<S::Ok, S::Error> { self.inner.serialize_i8(v) }
+
+        fn serialize_i16(self, v: i16) -> Result<S::Ok, S::Error> { self.inner.serialize_i16(v) }
+
+        fn serialize_i32(self, v: i32) -> Result<S::Ok, S::Error> { self.inner.serialize_i32(v) }
+
+        fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> { self.inner.serialize_i64(v) }
+
+        fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> { self.inner.serialize_i128(v) }
+
+        fn serialize_u8(self, v: u8) -> Result<S::Ok, S::Error> { self.inner.serialize_u8(v) }
+
+        fn serialize_u16(self, v: u16) -> Result<S::Ok, S::Error> { self.inner.serialize_u16(v) }
+
+        fn serialize_u32(self, v: u32) -> Result<S::Ok, S::Error> { self.inner.serialize_u32(v) }
+
+        fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> { self.inner.serialize_u64(v) }
+
+        fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> { self.inner.serialize_u128(v) }
+
+        fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> { self.inner.serialize_f32(v) }
+
+        fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> { self.inner.serialize_f64(v) }
+
+        fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> { self.inner.serialize_char(v) }
+
+        fn serialize_none(self) -> Result<S::Ok, S::Error> { self.inner.serialize_none() }
+
+        fn serialize_unit(self) -> Result<S::Ok, S::Error> { self.inner.serialize_unit() }
+
+        fn serialize_some<T: ?Sized + Serialize>(
+            self,
+            value: &T) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_some(&Masked(value, self.scrubber))
+        }
+
+        fn serialize_unit_struct(
+            self,
+            name: &'static str) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_unit_struct(name)
+        }
+
+        fn serialize_unit_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_unit_variant(name, variant_index, variant)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            name: &'static str,
+            value: &T) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_newtype_struct(name, &Masked(value, self.scrubber))
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            value: &T) -> Result<S::Ok, S::Error> {
+            self.inner.serialize_newtype_variant(name, variant_index, variant, &Masked(value, self.scrubber))
+        }
+
+        fn serialize_seq(
+            self,
+            len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_seq(len))
+        }
+
+        fn serialize_tuple(
+            self,
+            len: usize) -> Result<Self::SerializeTuple, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_tuple(len))
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            name: &'static str,
+            len: usize) -> Result<Self::SerializeTupleStruct, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_tuple_struct(name, len))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize) -> Result<Self::SerializeTupleVariant, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_tuple_variant(name, variant_index, variant, len))
+        }
+
+        fn serialize_map(
+            self,
+            len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_map(len))
+        }
+
+        fn serialize_struct(
+            self,
+            name: &'static str,
+            len: usize) -> Result<Self::SerializeStruct, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_struct(name, len))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize) -> Result<Self::SerializeStructVariant, S::Error> {
+            Self::compound(self.scrubber, self.inner.serialize_struct_variant(name, variant_index, variant, len))
+        }
+
+        fn is_human_readable(&self) -> bool { self.inner.is_human_readable() }
+    }
+
+    impl<C: ser::SerializeSeq> ser::SerializeSeq for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(
+            &mut self,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_element(&Masked(value, self.scrubber))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeTuple> ser::SerializeTuple for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(
+            &mut self,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_element(&Masked(value, self.scrubber))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_field(&Masked(value, self.scrubber))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_field(&Masked(value, self.scrubber))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeMap> ser::SerializeMap for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_key<T: ?Sized + Serialize>(
+            &mut self,
+            key: &T) -> Result<(), C::Error> {
+            self.inner.serialize_key(&Masked(key, self.scrubber))
+        }
+
+        fn serialize_value<T: ?Sized + Serialize>(
+            &mut self,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_value(&Masked(value, self.scrubber))
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeStruct> ser::SerializeStruct for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            key: &'static str,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_field(key, &Masked(value, self.scrubber))
+        }
+
+        fn skip_field(
+            &mut self,
+            key: &'static str) -> Result<(), C::Error> {
+            self.inner.skip_field(key)
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+
+    impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<'_, C> {
+        type Ok = C::Ok;
+        type Error = C::Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            key: &'static str,
+            value: &T) -> Result<(), C::Error> {
+            self.inner.serialize_field(key, &Masked(value, self.scrubber))
+        }
+
+        fn skip_field(
+            &mut self,
+            key: &'static str) -> Result<(), C::Error> {
+            self.inner.skip_field(key)
+        }
+
+        fn end(self) -> Result<C::Ok, C::Error> { self.inner.end() }
+    }
+}
@@ -0,0 +1,542 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::cross_company_correlating_id::generate_cross_company_correlating_id;
+use super::data_classification::DATA_CLASSES;
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+
+/* Same as the .NET fallback redaction token for regex matches */
+pub const DEFAULT_REDACTION_TOKEN: &str = "+++";
+
+/* Same as the .NET redaction token when correlating ids are generated */
+pub const C3ID_REDACTION_TEMPLATE: &str = "{id}:{c3id}";
+
+/* Names of the .NET rules, by rule ID */
+const RULE_NAMES: [(&str, &str); 36] = [
+    ("SEC000/000", "Unclassified32ByteBase64String"),
+    ("SEC000/001", "Unclassified64ByteBase64String"),
+    ("SEC000/002", "Unclassified16ByteHexadecimalString"),
+    ("SEC101/031", "NuGetApiKey"),
+    ("SEC101/050", "NpmAuthorKey"),
+    ("SEC101/055", "Pkcs12CertificatePrivateKeyBundle"),
+    ("SEC101/060", "LooseSasSecret"),
+    ("SEC101/061", "OAuth2BearerToken"),
+    ("SEC101/102", "AdoLegacyPat"),
+    ("SEC101/104", "AzureCosmosDBLegacyCredentials"),
+    ("SEC101/105", "AzureMessagingLegacyCredentials"),
+    ("SEC101/106", "AzureStorageAccountLegacyCredentials"),
+    ("SEC101/110", "AzureDatabricksPat"),
+    ("SEC101/127", "UrlCredentials"),
+    ("SEC101/152", "AzureStorageAccountIdentifiableKey"),
+    ("SEC101/154", "AzureCacheForRedisIdentifiableKey"),
+    ("SEC101/156", "AadClientAppIdentifiableCredentials"),
+    ("SEC101/158", "AzureFunctionIdentifiableKey"),
+    ("SEC101/160", "AzureCosmosDBIdentifiableKey"),
+    ("SEC101/163", "AzureBatchIdentifiableKey"),
+    ("SEC101/166", "AzureSearchIdentifiableQueryKey"),
+    ("SEC101/167", "AzureSearchIdentifiableAdminKey"),
+    ("SEC101/170", "AzureMLWebServiceClassicIdentifiableKey"),
+    ("SEC101/171", "AzureServiceBusIdentifiableKey"),
+    ("SEC101/172", "AzureEventHubIdentifiableKey"),
+    ("SEC101/173", "AzureRelayIdentifiableKey"),
+    ("SEC101/176", "AzureContainerRegistryIdentifiableKey"),
+    ("SEC101/178", "AzureIotHubIdentifiableKey"),
+    ("SEC101/179", "AzureIotDeviceProvisioningIdentifiableKey"),
+    ("SEC101/180", "AzureIotDeviceIdentifiableKey"),
+    ("SEC101/181", "AzureApimIdentifiableDirectManagementKey"),
+    ("SEC101/182", "AzureApimIdentifiableSubscriptionKey"),
+    ("SEC101/183", "AzureApimIdentifiableGatewayKey"),
+    ("SEC101/199", "AzureEventGridIdentifiableKey"),
+    ("SEC101/200", "UnclassifiedLegacyCommonAnnotatedSecurityKey"),
+    ("SEC101/528", "UnclassifiedJwt"),
+];
+
+/*
+ * Name of the .NET rule with id, None for rules without one, such as
+ * ones only in Rust, which are named by their id.
+ */
+pub fn rule_name(id: &str) -> Option<&'static str> {
+    RULE_NAMES
+        .iter()
+        .find(|(rule_id, _)| *rule_id == id)
+        .map(|(_, name)| *name)
+        .or_else(|| DATA_CLASSES.iter().find(|class| class.id() == id).map(|class| class.name()))
+}
+
+/*
+ * What needs redacting in input for each match, leftmost first, with
+ * overlapping and adjacent ranges absorbed into one like the .NET
+ * SecretMasker. Each range has the index of the match it starts with.
+ */
+fn redact_ranges(
+    input: &[u8],
+    matches: &[ScanMatch]) -> Vec<(usize, usize, usize)> {
+    let mut ranges: Vec<(usize, usize, usize)> = matches
+        .iter()
+        .enumerate()
+        .map(|(index, m)| {
+            let start = (m.redact_start() as usize).min(input.len());
+            let end = (start + m.redact_len() as usize).min(input.len());
+            (start, end, index)
+        })
+        .collect();
+
+    /* Leftmost first, then longest first */
+    ranges.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut merged: Vec<(usize, usize, usize)> = Vec::with_capacity(ranges.len());
+
+    for (start, end, index) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => { last.1 = last.1.max(end); },
+            _ => { merged.push((start, end, index)); },
+        }
+    }
+
+    merged
+}
+
+/*
+ * Replaces the part of each match that needs redacting with token. Like
+ * the .NET SecretMasker, overlapping and adjacent redactions are absorbed
+ * into one. Matches must be from scanning input as UTF8.
+ */
+pub fn redact_bytes(
+    input: &[u8],
+    matches: &[ScanMatch],
+    token: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+
+    for (start, end, _) in redact_ranges(input, matches) {
+        output.extend_from_slice(&input[index..start]);
+        output.extend_from_slice(token);
+        index = end;
+    }
+
+    output.extend_from_slice(&input[index..]);
+
+    output
+}
+
+/*
+ * Filler for a redacted byte, from the same alphabet so base64 stays
+ * base64 and base32 stays base32. Punctuation such as base64 padding is
+ * kept, anything that isn't ASCII becomes '*' so UTF8 stays valid.
+ */
+fn filler(b: u8) -> u8 {
+    match b {
+        b'a'..=b'z' => { b'a' },
+        b'A'..=b'Z' => { b'A' },
+        b'0'..=b'9' => { b'2' },
+        0x80.. => { b'*' },
+        _ => { b },
+    }
+}
+
+/*
+ * Fillers for what still matches after format-preserving redaction, such
+ * as tokens of loose rules that accept anything from their alphabet. Each
+ * is tried in turn, spaces end every token.
+ */
+const FALLBACK_FILLERS: [u8; 2] = [b'*', b' '];
+
+fn fill(
+    output: &mut [u8],
+    start: usize,
+    end: usize,
+    filler: impl Fn(u8) -> u8) {
+    for b in &mut output[start..end] {
+        *b = filler(*b);
+    }
+}
+
+/*
+ * Replaces what needs redacting with filler of the same length instead of
+ * a token, so offsets and sizes don't change and base64 or length checking
+ * parsers still accept the output. Letters and digits are replaced by ones
+ * of the same kind, see mask_preserving_format() to make sure the filler
+ * doesn't match again.
+ */
+pub fn redact_bytes_preserving_format(
+    input: &[u8],
+    matches: &[ScanMatch]) -> Vec<u8> {
+    let mut output = input.to_vec();
+
+    for (start, end, _) in redact_ranges(input, matches) {
+        fill(&mut output, start, end, filler);
+    }
+
+    output
+}
+
+/* Same as redact_bytes_preserving_format() for text */
+pub fn redact_preserving_format(
+    input: &str,
+    matches: &[ScanMatch]) -> String {
+    String::from_utf8(redact_bytes_preserving_format(input.as_bytes(), matches)).expect("Filler is ASCII")
+}
+
+/* Same as redact_bytes() for text, token must not split a character */
+pub fn redact(
+    input: &str,
+    matches: &[ScanMatch],
+    token: &str) -> String {
+    String::from_utf8_lossy(&redact_bytes(input.as_bytes(), matches, token.as_bytes())).into_owned()
+}
+
+/* Scans input with engine and redacts every match */
+pub fn mask(
+    engine: &ScanEngine,
+    input: &str) -> String {
+    let matches = engine.find_matches(input.as_bytes(), false);
+
+    redact(input, &matches, DEFAULT_REDACTION_TOKEN)
+}
+
+/*
+ * Scans input with engine and redacts every match preserving its format.
+ * The output is scanned again, and what still matches in what was redacted
+ * is replaced by fallback filler. Secrets whose redaction matches even
+ * then are replaced by DEFAULT_REDACTION_TOKEN instead, losing their format.
+ */
+pub fn mask_preserving_format(
+    engine: &ScanEngine,
+    input: &str) -> String {
+    let matches = engine.find_matches(input.as_bytes(), false);
+    let ranges = redact_ranges(input.as_bytes(), &matches);
+    let mut output = redact_bytes_preserving_format(input.as_bytes(), &matches);
+
+    let overlaps = |m: &ScanMatch, start: usize, end: usize| -> bool {
+        (m.start() as usize) < end && start < (m.start() + m.len()) as usize
+    };
+
+    /* What matches again where something was redacted */
+    let rematch = |output: &[u8]| -> Vec<ScanMatch> {
+        engine
+            .find_matches(output, false)
+            .into_iter()
+            .filter(|m| ranges.iter().any(|(start, end, _)| overlaps(m, *start, *end)))
+            .collect()
+    };
+
+    for fallback in FALLBACK_FILLERS {
+        let rematches = rematch(&output);
+
+        if rematches.is_empty() {
+            break;
+        }
+
+        /* Only what was redacted, the rest of the input is kept */
+        for (start, end, _) in redact_ranges(&output, &rematches) {
+            for (redact_start, redact_end, _) in &ranges {
+                let (start, end) = (start.max(*redact_start), end.min(*redact_end));
+
+                if start < end {
+                    fill(&mut output, start, end, |_| fallback);
+                }
+            }
+        }
+    }
+
+    let rematches = rematch(&output);
+
+    if rematches.is_empty() {
+        return String::from_utf8(output).expect("Filler is ASCII");
+    }
+
+    /* Output has the same offsets as input, so the matches apply to it */
+    let remaining: Vec<ScanMatch> = matches
+        .into_iter()
+        .filter(|m| rematches.iter().any(|rematch| overlaps(m, rematch.start() as usize, (rematch.start() + rematch.len()) as usize)))
+        .collect();
+
+    String::from_utf8(redact_bytes(&output, &remaining, DEFAULT_REDACTION_TOKEN.as_bytes())).expect("Filler is ASCII")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Id,
+    Name,
+    C3id,
+    First(usize),
+    Last(usize),
+}
+
+/*
+ * Builds the redaction token of each secret, so redacted secrets can be
+ * told apart without revealing them, like the .NET Detection.RedactionToken.
+ * Templates are text with placeholders, '{{' and '}}' for braces:
+ *
+ * {id}       the rule ID, such as SEC101/152
+ * {name}     the rule name, see rule_name(), or the rule ID
+ * {c3id}     the cross company correlating id of the secret
+ * {first:N}  the first N characters of the secret
+ * {last:N}   the last N characters of the secret
+ *
+ * At most a quarter of the secret is revealed by {first:N} and by {last:N}
+ * each, so half of it stays redacted however large N is.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedactionTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl RedactionTemplate {
+    /* The token for secret, found by m */
+    pub fn render(
+        &self,
+        m: &ScanMatch,
+        secret: &str) -> String {
+        let mut token = String::new();
+        let chars: Vec<char> = secret.chars().collect();
+        let max_reveal = chars.len() / 4;
+
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => { token.push_str(text); },
+                TemplatePart::Id => { token.push_str(m.name()); },
+                TemplatePart::Name => { token.push_str(rule_name(m.name()).unwrap_or(m.name())); },
+                TemplatePart::C3id => { token.push_str(&generate_cross_company_correlating_id(secret)); },
+                TemplatePart::First(count) => { token.extend(&chars[..*count.min(&max_reveal)]); },
+                TemplatePart::Last(count) => { token.extend(&chars[chars.len() - *count.min(&max_reveal)..]); },
+            }
+        }
+
+        token
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTemplateError {
+    message: String,
+}
+
+impl ParseTemplateError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for ParseTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseTemplateError {}
+
+impl FromStr for RedactionTemplate {
+    type Err = ParseTemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                text.push(c);
+                rest = &rest[2..];
+                continue;
+            }
+
+            if c == '}' {
+                return Err(ParseTemplateError::new(format!(
+                    "Unexpected '}}' at offset {} in '{}'",
+                    template.len() - rest.len(),
+                    template)));
+            }
+
+            if c != '{' {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+
+            let end = rest.find('}').ok_or_else(|| ParseTemplateError::new(format!(
+                "Unterminated placeholder at offset {} in '{}'",
+                template.len() - rest.len(),
+                template)))?;
+
+            let placeholder = &rest[1..end];
+
+            let count = |prefix: &str| -> Option<usize> {
+                placeholder.strip_prefix(prefix)?.parse().ok()
+            };
+
+            let part = match placeholder {
+                "id" => { TemplatePart::Id },
+                "name" => { TemplatePart::Name },
+                "c3id" => { TemplatePart::C3id },
+                _ => {
+                    match (count("first:"), count("last:")) {
+                        (Some(count), _) => { TemplatePart::First(count) },
+                        (_, Some(count)) => { TemplatePart::Last(count) },
+                        _ => {
+                            return Err(ParseTemplateError::new(format!(
+                                "Unknown placeholder '{{{}}}' in '{}'",
+                                placeholder,
+                                template)));
+                        },
+                    }
+                },
+            };
+
+            if !text.is_empty() {
+                parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+            }
+
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+impl fmt::Display for RedactionTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => { write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?; },
+                TemplatePart::Id => { write!(f, "{{id}}")?; },
+                TemplatePart::Name => { write!(f, "{{name}}")?; },
+                TemplatePart::C3id => { write!(f, "{{c3id}}")?; },
+                TemplatePart::First(count) => { write!(f, "{{first:{}}}", count)?; },
+                TemplatePart::Last(count) => { write!(f, "{{last:{}}}", count)?; },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn decode_utf16(
+    data: &[u8],
+    big_endian: bool) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| match big_endian {
+            true => { u16::from_be_bytes([unit[0], unit[1]]) },
+            false => { u16::from_le_bytes([unit[0], unit[1]]) },
+        })
+        .collect();
+
+    String::from_utf16_lossy(&units)
+}
+
+fn encode_utf16(
+    text: &str,
+    big_endian: bool) -> Vec<u8> {
+    text.encode_utf16()
+        .flat_map(|unit| match big_endian {
+            true => { unit.to_be_bytes() },
+            false => { unit.to_le_bytes() },
+        })
+        .collect()
+}
+
+/*
+ * The ranges of input to replace, and what to replace them with, for each
+ * redaction. render gives the token for the leftmost match of a redaction
+ * and the secret it redacts. Ranges are ordered and don't overlap. UTF16
+ * matches are in UTF16 BE when big_endian, else in UTF16 LE. The offset of
+ * a match can't tell, input can start anywhere in a UTF16 text.
+ */
+pub(crate) fn rendered_redactions(
+    input: &[u8],
+    matches: &[ScanMatch],
+    big_endian: bool,
+    mut render: impl FnMut(&ScanMatch, &str) -> String) -> Vec<(usize, usize, Vec<u8>)> {
+    redact_ranges(input, matches)
+        .into_iter()
+        .map(|(start, end, match_index)| {
+            let m = &matches[match_index];
+
+            match m.is_utf16() {
+                true => {
+                    /*
+                     * Matches are found as UTF16 LE, so in big endian they
+                     * start a byte after their first character.
+                     */
+                    let shift = (big_endian && start > 0) as usize;
+                    let (start, end) = (start - shift, end - shift);
+
+                    let secret = decode_utf16(&input[start..end], big_endian);
+                    (start, end, encode_utf16(&render(m, &secret), big_endian))
+                },
+                false => {
+                    let secret = String::from_utf8_lossy(&input[start..end]);
+                    (start, end, render(m, &secret).into_bytes())
+                },
+            }
+        })
+        .collect()
+}
+
+/* Same as rendered_redactions(), with tokens rendered from template */
+pub(crate) fn template_redactions(
+    input: &[u8],
+    matches: &[ScanMatch],
+    big_endian: bool,
+    template: &RedactionTemplate) -> Vec<(usize, usize, Vec<u8>)> {
+    rendered_redactions(input, matches, big_endian, |m, secret| template.render(m, secret))
+}
+
+/* input with each range replaced */
+pub(crate) fn apply_redactions(
+    input: &[u8],
+    redactions: &[(usize, usize, Vec<u8>)]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+
+    for (start, end, token) in redactions {
+        output.extend_from_slice(&input[index..(*start).max(index)]);
+        output.extend_from_slice(token);
+        index = index.max(*end);
+    }
+
+    output.extend_from_slice(&input[index..]);
+
+    output
+}
+
+/*
+ * Same as redact_bytes(), with a token rendered from template for each
+ * redaction. Redactions absorbing several matches are rendered for the
+ * leftmost one, the secret is everything they redact. Matches found in
+ * UTF16 are redacted with UTF16 LE tokens.
+ */
+pub fn redact_bytes_with_template(
+    input: &[u8],
+    matches: &[ScanMatch],
+    template: &RedactionTemplate) -> Vec<u8> {
+    apply_redactions(input, &template_redactions(input, matches, false, template))
+}
+
+/* Same as redact_bytes_with_template() for text */
+pub fn redact_with_template(
+    input: &str,
+    matches: &[ScanMatch],
+    template: &RedactionTemplate) -> String {
+    String::from_utf8_lossy(&redact_bytes_with_template(input.as_bytes(), matches, template)).into_owned()
+}
+
+/* Scans input with engine and redacts every match with template */
+pub fn mask_with_template(
+    engine: &ScanEngine,
+    input: &str,
+    template: &RedactionTemplate) -> String {
+    let matches = engine.find_matches(input.as_bytes(), false);
+
+    redact_with_template(input, &matches, template)
+}
@@ -0,0 +1,126 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use super::identifiable_scans::{PossibleScanMatch, ScanDefinition, ScanEngine, ScanOptions, ScanState};
+
+/*
+ * Shares a ScanEngine across threads and allows swapping in new rules while
+ * scans are in flight. Every scan pins the engine that was current when it
+ * started, so a swap only affects scans started afterwards.
+ */
+pub struct ScanEngineHandle {
+    engine: RwLock<Arc<ScanEngine>>,
+    generation: AtomicU64,
+}
+
+impl ScanEngineHandle {
+    pub fn new(engine: ScanEngine) -> Self {
+        Self {
+            engine: RwLock::new(Arc::new(engine)),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_options(options: ScanOptions) -> Self {
+        Self::new(ScanEngine::new(options))
+    }
+
+    /* The current engine, unaffected by later swaps */
+    pub fn snapshot(&self) -> Arc<ScanEngine> {
+        self.engine
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /* Incremented by every swap */
+    pub fn generation(&self) -> u64 { self.generation.load(Ordering::Acquire) }
+
+    /* Makes engine current and returns the previous one */
+    pub fn swap(
+        &self,
+        engine: ScanEngine) -> Arc<ScanEngine> {
+        let mut current = self.engine
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let previous = std::mem::replace(&mut *current, Arc::new(engine));
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        previous
+    }
+
+    /* Compiles options before taking the lock, scans are never blocked on it */
+    pub fn swap_options(
+        &self,
+        options: ScanOptions) -> Arc<ScanEngine> {
+        self.swap(ScanEngine::new(options))
+    }
+
+    /* Starts a scan pinned to the current engine */
+    pub fn scan(&self) -> PinnedScan {
+        let current = self.engine
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+
+        PinnedScan {
+            engine: current.clone(),
+            generation: self.generation(),
+            state: ScanState::default(),
+        }
+    }
+}
+
+impl Default for ScanEngineHandle {
+    fn default() -> Self {
+        Self::from_options(ScanOptions::default())
+    }
+}
+
+/*
+ * Like Scan, but shares the engine it was started with. Resetting keeps
+ * the same engine, start a new scan from the handle to pick up new rules.
+ */
+pub struct PinnedScan {
+    engine: Arc<ScanEngine>,
+    generation: u64,
+    state: ScanState,
+}
+
+impl PinnedScan {
+    pub fn engine(&self) -> &Arc<ScanEngine> { &self.engine }
+
+    /* Generation of the handle when the scan started */
+    pub fn generation(&self) -> u64 { self.generation }
+
+    pub fn has_possible_matches(&self) -> bool { self.state.has_possible_matches() }
+
+    pub fn possible_matches(&self) -> &Vec<PossibleScanMatch> { self.state.possible_matches() }
+
+    pub fn scan_defs(&self) -> &Vec<ScanDefinition> { self.engine.scan_defs() }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    pub fn parse_bytes(
+        &mut self,
+        data: &[u8]) {
+        self.engine.parse_bytes(
+            &mut self.state,
+            data);
+    }
+
+    pub fn parse_reader(
+        &mut self,
+        reader: &mut impl std::io::Read,
+        buf: &mut [u8]) -> std::io::Result<()> {
+        self.engine.parse_reader(
+            &mut self.state,
+            reader,
+            buf)
+    }
+}
@@ -0,0 +1,438 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use super::cross_company_correlating_id::generate_cross_company_correlating_id;
+use super::file_redaction;
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+use super::redaction;
+
+/* Vault tokens are [vault:<C3ID>] */
+pub const VAULT_TOKEN_PREFIX: &str = "[vault:";
+pub const VAULT_TOKEN_SUFFIX: &str = "]";
+
+/* First line of a vault file, also what entry keys are derived for */
+const VAULT_HEADER: &str = "security-utilities-vault 2";
+
+/* Base64 of the 15 bytes of a C3ID */
+const C3ID_LEN: usize = 20;
+
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+
+/* Every entry is sealed under a key of its own, so the nonce is fixed */
+const NONCE: [u8; 12] = [0; 12];
+
+pub struct VaultError {
+    message: String,
+}
+
+impl VaultError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Debug for VaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VaultError({:?})", self.message)
+    }
+}
+
+impl fmt::Display for VaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+impl From<io::Error> for VaultError {
+    fn from(error: io::Error) -> Self {
+        Self::new(error.to_string())
+    }
+}
+
+fn random_key() -> Result<[u8; KEY_LEN], VaultError> {
+    let mut key = [0; KEY_LEN];
+
+    getrandom::getrandom(&mut key)
+        .map_err(|e| VaultError::new(format!("Unable to generate a key: {}", e)))?;
+
+    Ok(key)
+}
+
+/*
+ * The X25519 key secrets are sealed to. Redacting only needs this, so
+ * processes that redact can't read the vault.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaultPublicKey([u8; KEY_LEN]);
+
+impl VaultPublicKey {
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> Self {
+        Self(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] { &self.0 }
+}
+
+/* The X25519 key that opens a vault, only VaultRehydrator needs it */
+#[derive(Clone)]
+pub struct VaultSecretKey(StaticSecret);
+
+impl VaultSecretKey {
+    /* A new random key */
+    pub fn generate() -> Result<Self, VaultError> {
+        Ok(Self(StaticSecret::from(random_key()?)))
+    }
+
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> Self {
+        Self(StaticSecret::from(key))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] { self.0.as_bytes() }
+
+    pub fn public_key(&self) -> VaultPublicKey {
+        VaultPublicKey(PublicKey::from(&self.0).to_bytes())
+    }
+}
+
+impl fmt::Debug for VaultSecretKey {
+    /* Never shows the key */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VaultSecretKey(..)")
+    }
+}
+
+/* The cipher of an entry, keyed from the X25519 shared secret and both public keys */
+fn entry_cipher(
+    shared: &[u8; KEY_LEN],
+    ephemeral: &[u8; KEY_LEN],
+    recipient: &[u8; KEY_LEN]) -> ChaCha20Poly1305 {
+    let salt = [ephemeral.as_slice(), recipient.as_slice()].concat();
+    let mut key = [0; KEY_LEN];
+
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(VAULT_HEADER.as_bytes(), &mut key)
+        .expect("Key length is valid for HKDF");
+
+    ChaCha20Poly1305::new(&key.into())
+}
+
+/*
+ * secret sealed to recipient under a random ephemeral key, as the
+ * ephemeral public key followed by the ChaCha20-Poly1305 ciphertext, with
+ * c3id authenticated.
+ */
+fn seal(
+    recipient: &VaultPublicKey,
+    c3id: &str,
+    secret: &str) -> Result<Vec<u8>, VaultError> {
+    let ephemeral = StaticSecret::from(random_key()?);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient.0));
+
+    if !shared.was_contributory() {
+        return Err(VaultError::new("Invalid vault public key".to_string()));
+    }
+
+    let ciphertext = entry_cipher(shared.as_bytes(), &ephemeral_public, &recipient.0)
+        .encrypt(&NONCE.into(), Payload { msg: secret.as_bytes(), aad: c3id.as_bytes() })
+        .map_err(|_| VaultError::new(format!("Unable to seal the entry for {}", c3id)))?;
+
+    Ok([ephemeral_public.as_slice(), &ciphertext].concat())
+}
+
+/* The secret sealed to key, checked against its C3ID */
+fn open_sealed(
+    key: &VaultSecretKey,
+    c3id: &str,
+    sealed: &[u8]) -> Result<String, VaultError> {
+    let tampered = || VaultError::new(format!("Wrong key or tampered entry for {}", c3id));
+
+    let (ephemeral, ciphertext) = sealed.split_at(KEY_LEN);
+    let ephemeral: [u8; KEY_LEN] = ephemeral.try_into().expect("Split at KEY_LEN");
+    let shared = key.0.diffie_hellman(&PublicKey::from(ephemeral));
+
+    if !shared.was_contributory() {
+        return Err(tampered());
+    }
+
+    let plaintext = entry_cipher(shared.as_bytes(), &ephemeral, key.public_key().as_bytes())
+        .decrypt(&NONCE.into(), Payload { msg: ciphertext, aad: c3id.as_bytes() })
+        .map_err(|_| tampered())?;
+
+    String::from_utf8(plaintext)
+        .ok()
+        .filter(|secret| generate_cross_company_correlating_id(secret) == c3id)
+        .ok_or_else(|| VaultError::new(format!("Entry for {} does not match its C3ID", c3id)))
+}
+
+/* The token a secret is replaced with, the same for every occurrence */
+pub fn vault_token(secret: &str) -> String {
+    format!("{}{}{}", VAULT_TOKEN_PREFIX, generate_cross_company_correlating_id(secret), VAULT_TOKEN_SUFFIX)
+}
+
+/*
+ * A vault file, one entry per secret keyed by its C3ID, each sealed to the
+ * vault public key, see seal().
+ */
+struct Store {
+    path: PathBuf,
+    /* C3ID to ephemeral public key and ciphertext */
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl Store {
+    /* Missing files are empty vaults */
+    fn open(path: &Path) -> Result<Self, VaultError> {
+        let mut store = Self {
+            path: path.to_path_buf(),
+            entries: BTreeMap::new(),
+        };
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => { contents },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => { return Ok(store); },
+            Err(e) => { return Err(e.into()); },
+        };
+
+        let mut lines = contents.lines();
+
+        if lines.next() != Some(VAULT_HEADER) {
+            return Err(VaultError::new(format!("{} is not a vault", path.display())));
+        }
+
+        for (number, line) in lines.enumerate() {
+            let entry = line
+                .split_once(' ')
+                .and_then(|(c3id, sealed)| Some((c3id, STANDARD.decode(sealed).ok()?)))
+                .filter(|(c3id, sealed)| c3id.len() == C3ID_LEN && sealed.len() >= KEY_LEN + TAG_LEN);
+
+            match entry {
+                Some((c3id, sealed)) => { store.entries.insert(c3id.to_string(), sealed); },
+                None => {
+                    return Err(VaultError::new(format!(
+                        "Malformed entry on line {} of {}",
+                        number + 2,
+                        path.display())));
+                },
+            }
+        }
+
+        Ok(store)
+    }
+
+    /*
+     * Writes the entries, with those saved by others since the vault was
+     * read, holding a lock on a file next to the vault so saves from other
+     * vaults and processes aren't lost. New vaults are only readable by
+     * their owner, the lock file is left in place.
+     */
+    fn save(&mut self) -> Result<(), VaultError> {
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+
+        let mut options = OpenOptions::new();
+
+        options.write(true).create(true).truncate(false);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let lock = options.open(&lock_path)?;
+        lock.lock()?;
+
+        for (c3id, sealed) in Self::open(&self.path)?.entries {
+            self.entries.entry(c3id).or_insert(sealed);
+        }
+
+        let mut contents = format!("{}\n", VAULT_HEADER);
+
+        for (c3id, sealed) in &self.entries {
+            contents.push_str(&format!("{} {}\n", c3id, STANDARD.encode(sealed)));
+        }
+
+        if !self.path.exists() {
+            let mut options = OpenOptions::new();
+
+            options.write(true).create_new(true);
+
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+            options.open(&self.path)?;
+        }
+
+        file_redaction::write_atomically(&self.path, contents.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/*
+ * Reversible redaction. Secrets are replaced with vault tokens, see
+ * vault_token(), and recorded sealed to a VaultPublicKey so VaultRehydrator
+ * can restore them with the VaultSecretKey. Secrets can't be read back
+ * through a SecretVault, nor with anything it holds.
+ */
+pub struct SecretVault {
+    store: Store,
+    key: VaultPublicKey,
+}
+
+impl SecretVault {
+    /* Opens the vault at path, changes are written by save() */
+    pub fn open(
+        path: impl AsRef<Path>,
+        key: &VaultPublicKey) -> Result<Self, VaultError> {
+        Ok(Self {
+            store: Store::open(path.as_ref())?,
+            key: *key,
+        })
+    }
+
+    pub fn path(&self) -> &Path { &self.store.path }
+
+    /* Number of distinct secrets recorded */
+    pub fn len(&self) -> usize { self.store.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.store.entries.is_empty() }
+
+    /*
+     * Same as redaction::redact_bytes() with a vault token for each
+     * redaction, recording the secrets. Matches found in UTF16 are
+     * redacted with UTF16 LE tokens.
+     */
+    pub fn redact_bytes(
+        &mut self,
+        input: &[u8],
+        matches: &[ScanMatch]) -> Result<Vec<u8>, VaultError> {
+        let mut secrets = Vec::new();
+
+        let redactions = redaction::rendered_redactions(input, matches, false, |_, secret| {
+            secrets.push(secret.to_string());
+            vault_token(secret)
+        });
+
+        for secret in secrets {
+            let c3id = generate_cross_company_correlating_id(&secret);
+
+            if !self.store.entries.contains_key(&c3id) {
+                let sealed = seal(&self.key, &c3id, &secret)?;
+                self.store.entries.insert(c3id, sealed);
+            }
+        }
+
+        Ok(redaction::apply_redactions(input, &redactions))
+    }
+
+    /* Same as redact_bytes() for text */
+    pub fn redact(
+        &mut self,
+        input: &str,
+        matches: &[ScanMatch]) -> Result<String, VaultError> {
+        let output = self.redact_bytes(input.as_bytes(), matches)?;
+
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /* Scans input with engine and redacts every match with a vault token */
+    pub fn mask(
+        &mut self,
+        engine: &ScanEngine,
+        input: &str) -> Result<String, VaultError> {
+        let matches = engine.find_matches(input.as_bytes(), false);
+
+        self.redact(input, &matches)
+    }
+
+    /*
+     * Writes the vault atomically, along with what other vaults saved to
+     * the same file since this one was opened
+     */
+    pub fn save(&mut self) -> Result<(), VaultError> {
+        self.store.save()
+    }
+}
+
+/*
+ * Restores secrets redacted by SecretVault. This is the privileged side of
+ * the vault, only it holds the VaultSecretKey.
+ */
+pub struct VaultRehydrator {
+    store: Store,
+    key: VaultSecretKey,
+}
+
+impl VaultRehydrator {
+    pub fn open(
+        path: impl AsRef<Path>,
+        key: &VaultSecretKey) -> Result<Self, VaultError> {
+        Ok(Self {
+            store: Store::open(path.as_ref())?,
+            key: key.clone(),
+        })
+    }
+
+    /* The secret recorded for c3id, None when the vault has no such entry */
+    pub fn reveal(
+        &self,
+        c3id: &str) -> Result<Option<String>, VaultError> {
+        match self.store.entries.get(c3id) {
+            Some(sealed) => { open_sealed(&self.key, c3id, sealed).map(Some) },
+            None => { Ok(None) },
+        }
+    }
+
+    /*
+     * text with every vault token restored to its secret. Tokens the vault
+     * has no entry for are left as they are.
+     */
+    pub fn rehydrate(
+        &self,
+        text: &str) -> Result<String, VaultError> {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(pos) = rest.find(VAULT_TOKEN_PREFIX) {
+            let token_start = pos + VAULT_TOKEN_PREFIX.len();
+            let c3id = rest.get(token_start..token_start + C3ID_LEN);
+            let suffix = rest.get(token_start + C3ID_LEN..token_start + C3ID_LEN + VAULT_TOKEN_SUFFIX.len());
+
+            let secret = match (c3id, suffix) {
+                (Some(c3id), Some(VAULT_TOKEN_SUFFIX)) => { self.reveal(c3id)? },
+                _ => { None },
+            };
+
+            match secret {
+                Some(secret) => {
+                    output.push_str(&rest[..pos]);
+                    output.push_str(&secret);
+                    rest = &rest[token_start + C3ID_LEN + VAULT_TOKEN_SUFFIX.len()..];
+                },
+                None => {
+                    output.push_str(&rest[..token_start]);
+                    rest = &rest[token_start..];
+                },
+            }
+        }
+
+        output.push_str(rest);
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,887 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fmt;
+
+use super::identifiable_scans::{ScanEngine, ScanMatch};
+use super::json::{self, pointer_segment};
+use super::literal_masker::json_escape;
+use super::redaction::{self, RedactionTemplate};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuredRedactionError {
+    message: String,
+}
+
+impl StructuredRedactionError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for StructuredRedactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for StructuredRedactionError {}
+
+/* A secret found in a string value */
+pub struct StructuredFinding {
+    document: usize,
+    pointer: String,
+    scan_match: ScanMatch,
+}
+
+impl StructuredFinding {
+    /* Index of the YAML document the value is in, always 0 for JSON */
+    pub fn document(&self) -> usize { self.document }
+
+    /* RFC 6901 JSON pointer to the value, such as /servers/0/password */
+    pub fn pointer(&self) -> &str { &self.pointer }
+
+    /* The match, its start is in the value as parsed, escapes decoded */
+    pub fn scan_match(&self) -> &ScanMatch { &self.scan_match }
+}
+
+pub struct StructuredRedaction {
+    output: String,
+    findings: Vec<StructuredFinding>,
+}
+
+impl StructuredRedaction {
+    /* The document with secrets redacted, unchanged apart from their values */
+    pub fn output(&self) -> &str { &self.output }
+
+    pub fn into_output(self) -> String { self.output }
+
+    pub fn findings(&self) -> &[StructuredFinding] { &self.findings }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScalarStyle {
+    Json,
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /* Literal or folded block, with the indentation of its lines */
+    Block(usize),
+}
+
+/* A string value and the range of text it was read from */
+struct Scalar {
+    document: usize,
+    pointer: String,
+    start: usize,
+    end: usize,
+    value: String,
+    style: ScalarStyle,
+}
+
+/*
+ * Scans each string value with engine and redacts what it finds with
+ * template, re-encoding the value so the document stays valid. Member
+ * names, numbers and everything between values are kept as they are.
+ */
+fn redact_scalars(
+    engine: &ScanEngine,
+    text: &str,
+    scalars: Vec<Scalar>,
+    template: &RedactionTemplate) -> StructuredRedaction {
+    let line_break = match text.contains("\r\n") {
+        true => { "\r\n" },
+        false => { "\n" },
+    };
+
+    let mut output = String::with_capacity(text.len());
+    let mut findings = Vec::new();
+    let mut index = 0;
+
+    for scalar in scalars {
+        let matches = engine.find_matches(scalar.value.as_bytes(), false);
+
+        if matches.is_empty() {
+            continue;
+        }
+
+        let redacted = redaction::redact_with_template(&scalar.value, &matches, template);
+
+        output.push_str(&text[index..scalar.start]);
+        output.push_str(&encode_scalar(&redacted, scalar.style, line_break));
+        index = scalar.end;
+
+        findings.extend(matches.into_iter().map(|scan_match| StructuredFinding {
+            document: scalar.document,
+            pointer: scalar.pointer.clone(),
+            scan_match,
+        }));
+    }
+
+    output.push_str(&text[index..]);
+
+    StructuredRedaction {
+        output,
+        findings,
+    }
+}
+
+/* Plain scalars that would read back as something else are quoted */
+fn is_plain_safe(value: &str) -> bool {
+    let first = match value.chars().next() {
+        Some(first) => { first },
+        None => { return false; },
+    };
+
+    !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && value.trim() == value
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !value.chars().any(char::is_control)
+}
+
+fn yaml_double_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => { quoted.push_str("\\\""); },
+            '\\' => { quoted.push_str("\\\\"); },
+            '\n' => { quoted.push_str("\\n"); },
+            '\r' => { quoted.push_str("\\r"); },
+            '\t' => { quoted.push_str("\\t"); },
+            c if c.is_control() => { quoted.push_str(&format!("\\u{:04X}", c as u32)); },
+            c => { quoted.push(c); },
+        }
+    }
+
+    quoted.push('"');
+
+    quoted
+}
+
+fn encode_scalar(
+    value: &str,
+    style: ScalarStyle,
+    line_break: &str) -> String {
+    match style {
+        ScalarStyle::Json => { format!("\"{}\"", json_escape(value)) },
+        ScalarStyle::Plain if is_plain_safe(value) => { value.to_string() },
+        ScalarStyle::Plain | ScalarStyle::DoubleQuoted => { yaml_double_quote(value) },
+        ScalarStyle::SingleQuoted if value.chars().any(char::is_control) => { yaml_double_quote(value) },
+        ScalarStyle::SingleQuoted => { format!("'{}'", value.replace('\'', "''")) },
+        ScalarStyle::Block(indent) => {
+            value
+                .split('\n')
+                .map(|line| match line.is_empty() {
+                    true => { String::new() },
+                    false => { format!("{}{}", " ".repeat(indent), line) },
+                })
+                .collect::<Vec<_>>()
+                .join(line_break)
+        },
+    }
+}
+
+/*
+ * Redacts secrets in the string values of a JSON document. Each finding
+ * has the JSON pointer of its value.
+ */
+pub fn redact_json(
+    engine: &ScanEngine,
+    text: &str,
+    template: &RedactionTemplate) -> Result<StructuredRedaction, StructuredRedactionError> {
+    let strings = json::string_values(text)
+        .ok_or_else(|| StructuredRedactionError::new("Input is not valid JSON".to_string()))?;
+
+    let scalars = strings
+        .into_iter()
+        .map(|string| Scalar {
+            document: 0,
+            pointer: string.pointer,
+            start: string.start,
+            end: string.end,
+            value: string.value,
+            style: ScalarStyle::Json,
+        })
+        .collect();
+
+    Ok(redact_scalars(engine, text, scalars, template))
+}
+
+/*
+ * Redacts secrets in the scalar values of a YAML stream, see YamlReader
+ * for what is supported. Each finding has its document and the JSON
+ * pointer of its value.
+ */
+pub fn redact_yaml(
+    engine: &ScanEngine,
+    text: &str,
+    template: &RedactionTemplate) -> Result<StructuredRedaction, StructuredRedactionError> {
+    let scalars = YamlReader::new(text).read()?;
+
+    Ok(redact_scalars(engine, text, scalars, template))
+}
+
+/* A mapping or sequence, and the entry being read */
+struct Level {
+    indent: usize,
+    sequence: bool,
+    /* Items seen so far, for sequences */
+    items: usize,
+    key: String,
+}
+
+/*
+ * Reader for block style YAML: mappings, sequences, plain, quoted and
+ * block scalars, comments and multiple documents. Anchors and tags are
+ * skipped, and so are aliases and flow collections, such as {} and [],
+ * whose values aren't scanned. Complex keys and quoted scalars spanning
+ * lines are rejected rather than misread.
+ */
+struct YamlReader<'a> {
+    text: &'a str,
+    /* Offset and text of each line, without the line break */
+    lines: Vec<(usize, &'a str)>,
+    line: usize,
+    document: usize,
+    stack: Vec<Level>,
+    /* The previous line ended with a key or item and no value */
+    expect_nested: bool,
+    /* The document's root node has been read, or started */
+    root_read: bool,
+    scalars: Vec<Scalar>,
+}
+
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+fn is_comment_or_empty(content: &str) -> bool {
+    content.is_empty() || content.starts_with('#')
+}
+
+/* Length of the anchor and tag at the start of content, and the spaces after them */
+fn properties_len(content: &str) -> usize {
+    let mut len = 0;
+
+    while content[len..].starts_with(['&', '!']) {
+        let property = content[len..].find(' ').unwrap_or(content.len() - len);
+        let rest = &content[len + property..];
+
+        len = content.len() - rest.trim_start_matches(' ').len();
+    }
+
+    len
+}
+
+/* Spaces at the start of line */
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+impl<'a> YamlReader<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut lines = Vec::new();
+        let mut offset = 0;
+
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches('\n').trim_end_matches('\r');
+
+            lines.push((offset, content));
+            offset += line.len();
+        }
+
+        Self {
+            text,
+            lines,
+            line: 0,
+            document: 0,
+            stack: Vec::new(),
+            expect_nested: false,
+            root_read: false,
+            scalars: Vec::new(),
+        }
+    }
+
+    fn error(
+        &self,
+        message: &str) -> StructuredRedactionError {
+        StructuredRedactionError::new(format!("{} at line {}", message, self.line + 1))
+    }
+
+    fn pointer(&self) -> String {
+        self.stack
+            .iter()
+            .map(|level| match level.sequence {
+                true => { format!("/{}", level.items - 1) },
+                false => { format!("/{}", pointer_segment(&level.key)) },
+            })
+            .collect()
+    }
+
+    fn read(mut self) -> Result<Vec<Scalar>, StructuredRedactionError> {
+        let mut started = false;
+
+        while self.line < self.lines.len() {
+            let (offset, line) = self.lines[self.line];
+            let indent = indentation(line);
+            let content = line[indent..].trim_end();
+
+            if content.starts_with('\t') {
+                return Err(self.error("Tabs are not supported for indentation"));
+            }
+
+            if is_comment_or_empty(content) {
+                self.line += 1;
+                continue;
+            }
+
+            if indent == 0 && (content == "---" || content.starts_with("--- ") || content == "...") {
+                let rest = content.trim_start_matches(['-', '.']).trim_start();
+
+                if !is_comment_or_empty(rest) {
+                    return Err(self.error("Values on a document marker are not supported"));
+                }
+
+                if started && content.starts_with('-') {
+                    self.document += 1;
+                }
+
+                self.stack.clear();
+                self.expect_nested = false;
+                self.root_read = false;
+                started = content.starts_with('-');
+                self.line += 1;
+                continue;
+            }
+
+            if indent == 0 && content.starts_with('%') && !started {
+                self.line += 1;
+                continue;
+            }
+
+            started = true;
+            self.node(offset + indent, indent, content)?;
+        }
+
+        Ok(self.scalars)
+    }
+
+    /* A line with content, at offset in the text */
+    fn node(
+        &mut self,
+        offset: usize,
+        indent: usize,
+        content: &'a str) -> Result<(), StructuredRedactionError> {
+        let item = is_sequence_item(content);
+
+        /* A sequence can share the indentation of the key it is the value of */
+        while let Some(top) = self.stack.last() {
+            if top.indent > indent || (top.indent == indent && top.sequence && !item && !self.expect_nested) {
+                self.stack.pop();
+            } else {
+                break;
+            }
+        }
+
+        let nested = match self.stack.last() {
+            Some(top) if top.indent == indent => {
+                match (self.expect_nested, top.sequence, item) {
+                    (true, false, true) => { true },
+                    (_, sequence, item) if sequence == item => { false },
+                    _ => { return Err(self.error("Unexpected indentation")); },
+                }
+            },
+            _ => { true },
+        };
+
+        if nested {
+            if !self.expect_nested && !self.stack.is_empty() {
+                return Err(self.error("Scalars spanning lines are not supported"));
+            }
+
+            if self.stack.is_empty() && self.root_read {
+                return Err(self.error("Unexpected indentation"));
+            }
+
+            if !item && self.key_end(content).is_none() {
+                /* A value on the line after its key, or a document that is a scalar */
+                self.expect_nested = false;
+                self.root_read = true;
+
+                return self.value(offset, content, indent.saturating_sub(1));
+            }
+
+            self.root_read = true;
+            self.stack.push(Level {
+                indent,
+                sequence: item,
+                items: 0,
+                key: String::new(),
+            });
+        }
+
+        self.expect_nested = false;
+        self.entry(offset, indent, content)
+    }
+
+    /* An item of the innermost sequence or a member of the innermost mapping */
+    fn entry(
+        &mut self,
+        offset: usize,
+        indent: usize,
+        content: &'a str) -> Result<(), StructuredRedactionError> {
+        let level_indent = indent;
+        let top = self.stack.last_mut().expect("An entry is in a collection");
+
+        if top.sequence {
+            top.items += 1;
+
+            let rest = content[1..].trim_start();
+            let column = indent + (content.len() - rest.len());
+
+            if is_comment_or_empty(rest) {
+                self.expect_nested = true;
+                self.line += 1;
+                return Ok(());
+            }
+
+            /* An item that is itself a collection starts on the item's line */
+            if is_sequence_item(rest) || self.key_end(rest).is_some() {
+                self.stack.push(Level {
+                    indent: column,
+                    sequence: is_sequence_item(rest),
+                    items: 0,
+                    key: String::new(),
+                });
+
+                return self.entry(offset + column - indent, column, rest);
+            }
+
+            return self.value(offset + column - indent, rest, level_indent);
+        }
+
+        let (key, key_len) = match self.key_end(content) {
+            Some(key_end) => { key_end },
+            None => { return Err(self.error("Expected a mapping key")); },
+        };
+
+        self.stack.last_mut().expect("An entry is in a collection").key = key;
+
+        let rest = content[key_len..].trim_start();
+
+        if is_comment_or_empty(rest) {
+            self.expect_nested = true;
+            self.line += 1;
+            return Ok(());
+        }
+
+        self.value(offset + content.len() - rest.len(), rest, level_indent)
+    }
+
+    /* The key of a mapping entry and the length up to the ':', if content is one */
+    fn key_end(
+        &self,
+        content: &str) -> Option<(String, usize)> {
+        let properties = properties_len(content);
+        let (key, len) = self.key_end_after_properties(&content[properties..])?;
+
+        Some((key, properties + len))
+    }
+
+    fn key_end_after_properties(
+        &self,
+        content: &str) -> Option<(String, usize)> {
+        let (key, len) = match content.chars().next()? {
+            '"' => { double_quoted(content)? },
+            '\'' => { single_quoted(content)? },
+            c if "[]{}&*!|>%@`#?".contains(c) => { return None; },
+            _ => {
+                let bytes = content.as_bytes();
+                let colon = (0..bytes.len()).find(|&i| {
+                    bytes[i] == b':' && bytes.get(i + 1).is_none_or(|b| *b == b' ')
+                })?;
+
+                if content[..colon].contains(" #") {
+                    return None;
+                }
+
+                (content[..colon].trim_end().to_string(), colon)
+            },
+        };
+
+        let after = &content[len..];
+        let colon = after.len() - after.trim_start().len();
+
+        match after[colon..].strip_prefix(':') {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => { Some((key, len + colon + 1)) },
+            _ => { None },
+        }
+    }
+
+    /*
+     * A value starting at offset, content being the rest of its line.
+     * parent_indent is that of the entry, block scalars are indented more.
+     */
+    fn value(
+        &mut self,
+        offset: usize,
+        content: &str,
+        parent_indent: usize) -> Result<(), StructuredRedactionError> {
+        let properties = properties_len(content);
+        let offset = offset + properties;
+        let content = &content[properties..];
+
+        /* An anchor or tag of a collection on the following lines */
+        if is_comment_or_empty(content) {
+            self.expect_nested = true;
+            self.line += 1;
+            return Ok(());
+        }
+
+        let pointer = self.pointer();
+        let first = content.chars().next().expect("Values aren't empty");
+
+        let (value, len, style) = match first {
+            '"' => {
+                let (value, len) = double_quoted(content)
+                    .ok_or_else(|| self.error("Unterminated or multi-line double quoted scalar"))?;
+
+                (value, len, ScalarStyle::DoubleQuoted)
+            },
+            '\'' => {
+                let (value, len) = single_quoted(content)
+                    .ok_or_else(|| self.error("Unterminated or multi-line single quoted scalar"))?;
+
+                (value, len, ScalarStyle::SingleQuoted)
+            },
+            '|' | '>' => { return self.block_scalar(content, parent_indent, pointer); },
+            '[' | '{' => { return self.flow_collection(offset); },
+            '*' => {
+                let len = content.find(' ').unwrap_or(content.len());
+
+                if !is_comment_or_empty(content[len..].trim_start()) {
+                    return Err(self.error("Unexpected text after an alias"));
+                }
+
+                self.line += 1;
+                return Ok(());
+            },
+            '@' | '`' => { return Err(self.error("Reserved indicator")); },
+            '?' if content == "?" || content.starts_with("? ") => {
+                return Err(self.error("Complex keys are not supported"));
+            },
+            _ => { return self.plain_scalar(offset, content, parent_indent, pointer); },
+        };
+
+        if !is_comment_or_empty(content[len..].trim_start()) || (len < content.len() && !content[len..].starts_with(' ')) {
+            return Err(self.error("Unexpected text after a scalar"));
+        }
+
+        self.scalars.push(Scalar {
+            document: self.document,
+            pointer,
+            start: offset,
+            end: offset + len,
+            value,
+            style,
+        });
+
+        self.line += 1;
+
+        Ok(())
+    }
+
+    /*
+     * A plain scalar, which continues on following lines indented more
+     * than its parent. Those are folded into one line, and empty lines
+     * into line breaks.
+     */
+    fn plain_scalar(
+        &mut self,
+        offset: usize,
+        content: &str,
+        parent_indent: usize,
+        pointer: String) -> Result<(), StructuredRedactionError> {
+        let comment = content.find(" #");
+        let mut value = content[..comment.unwrap_or(content.len())].trim_end().to_string();
+        let mut end = offset + value.len();
+        let mut last_line = self.line;
+        let mut next = self.line + 1;
+        let mut breaks = 0;
+
+        while comment.is_none() && next < self.lines.len() {
+            let (line_offset, line) = self.lines[next];
+            let indent = indentation(line);
+            let text = line[indent..].trim_end();
+
+            if text.is_empty() {
+                breaks += 1;
+                next += 1;
+                continue;
+            }
+
+            if indent <= parent_indent || text.starts_with('#') {
+                break;
+            }
+
+            let comment = text.find(" #");
+            let text = text[..comment.unwrap_or(text.len())].trim_end();
+
+            if text.contains(": ") || text.ends_with(':') {
+                self.line = next;
+                return Err(self.error("Mapping values are not allowed in plain scalars"));
+            }
+
+            match breaks {
+                0 => { value.push(' '); },
+                _ => { value.push_str(&"\n".repeat(breaks)); },
+            }
+
+            value.push_str(text);
+            end = line_offset + indent + text.len();
+            last_line = next;
+            breaks = 0;
+            next += 1;
+
+            if comment.is_some() {
+                break;
+            }
+        }
+
+        self.scalars.push(Scalar {
+            document: self.document,
+            pointer,
+            start: offset,
+            end,
+            value,
+            style: ScalarStyle::Plain,
+        });
+
+        self.line = last_line + 1;
+
+        Ok(())
+    }
+
+    /*
+     * Skips a flow collection starting at offset, which can span lines.
+     * Nothing in it is scanned.
+     */
+    fn flow_collection(
+        &mut self,
+        offset: usize) -> Result<(), StructuredRedactionError> {
+        let bytes = self.text.as_bytes();
+        let mut open = Vec::new();
+        let mut quote = None;
+        let mut i = offset;
+
+        let end = loop {
+            let b = match bytes.get(i) {
+                Some(b) => { *b },
+                None => { return Err(self.error("Unterminated flow collection")); },
+            };
+
+            let after_space = i > offset && matches!(bytes[i - 1], b' ' | b'\t' | b'\r' | b'\n');
+
+            match (quote, b) {
+                (Some(b'\''), b'\'') if bytes.get(i + 1) == Some(&b'\'') => { i += 1; },
+                (Some(b'"'), b'\\') => { i += 1; },
+                (Some(q), b) if q == b => { quote = None; },
+                (Some(_), _) => {},
+                (None, b'[' | b'{') => { open.push(b); },
+                (None, b']' | b'}') => {
+                    let expected = match b {
+                        b']' => { b'[' },
+                        _ => { b'{' },
+                    };
+
+                    if open.pop() != Some(expected) {
+                        return Err(self.error("Mismatched flow collection"));
+                    }
+
+                    if open.is_empty() {
+                        break i + 1;
+                    }
+                },
+                (None, b'\'' | b'"') if after_space || matches!(bytes[i - 1], b'[' | b'{' | b',' | b':') => {
+                    quote = Some(b);
+                },
+                (None, b'#') if after_space => {
+                    i += bytes[i..].iter().position(|b| *b == b'\n').unwrap_or(bytes.len() - i);
+                    continue;
+                },
+                _ => {},
+            }
+
+            i += 1;
+        };
+
+        let last_line = self.lines.partition_point(|(line_offset, _)| *line_offset < end) - 1;
+        let (line_offset, line) = self.lines[last_line];
+        let rest = &line[end - line_offset..];
+
+        self.line = last_line;
+
+        if !is_comment_or_empty(rest.trim_start()) || !(rest.is_empty() || rest.starts_with(' ')) {
+            return Err(self.error("Unexpected text after a flow collection"));
+        }
+
+        self.line += 1;
+
+        Ok(())
+    }
+
+    /* A literal or folded scalar, its lines are read as they are */
+    fn block_scalar(
+        &mut self,
+        header: &str,
+        parent_indent: usize,
+        pointer: String) -> Result<(), StructuredRedactionError> {
+        let indicators = header[1..].split(" #").next().unwrap_or("").trim_end();
+        let explicit_indent = indicators.chars().find_map(|c| c.to_digit(10));
+
+        if indicators.chars().any(|c| !matches!(c, '+' | '-' | '1'..='9')) || indicators.len() > 2 {
+            return Err(self.error("Malformed block scalar header"));
+        }
+
+        self.line += 1;
+
+        let first_line = self.line;
+        let block_indent = match explicit_indent {
+            Some(explicit) => { parent_indent + explicit as usize },
+            None => {
+                self.lines[first_line..]
+                    .iter()
+                    .map(|(_, line)| *line)
+                    .find(|line| !line.trim().is_empty())
+                    .map(indentation)
+                    .filter(|indent| *indent > parent_indent)
+                    .unwrap_or(parent_indent + 1)
+            },
+        };
+
+        let mut last_line = None;
+
+        while self.line < self.lines.len() {
+            let line = self.lines[self.line].1;
+
+            if !line.trim().is_empty() {
+                if indentation(line) < block_indent {
+                    break;
+                }
+
+                last_line = Some(self.line);
+            }
+
+            self.line += 1;
+        }
+
+        /* Trailing empty lines are left as they are */
+        let last_line = match last_line {
+            Some(last_line) => { last_line },
+            None => {
+                self.line = first_line;
+                return Ok(());
+            },
+        };
+
+        self.line = last_line + 1;
+
+        let value = self.lines[first_line..=last_line]
+            .iter()
+            .map(|(_, line)| line.get(block_indent..).unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (last_offset, last_text) = self.lines[last_line];
+
+        self.scalars.push(Scalar {
+            document: self.document,
+            pointer,
+            start: self.lines[first_line].0,
+            end: last_offset + last_text.len(),
+            value,
+            style: ScalarStyle::Block(block_indent),
+        });
+
+        Ok(())
+    }
+}
+
+/* The value and length of a double quoted scalar at the start of content */
+fn double_quoted(content: &str) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = content.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => { return Some((value, i + 1)); },
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+
+                let hex_len = match escaped {
+                    'x' => { 2 },
+                    'u' => { 4 },
+                    'U' => { 8 },
+                    _ => { 0 },
+                };
+
+                if hex_len > 0 {
+                    let hex: String = (0..hex_len).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+
+                    value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                    continue;
+                }
+
+                value.push(match escaped {
+                    '0' => { '\0' },
+                    'a' => { '\u{07}' },
+                    'b' => { '\u{08}' },
+                    't' | '\t' => { '\t' },
+                    'n' => { '\n' },
+                    'v' => { '\u{0B}' },
+                    'f' => { '\u{0C}' },
+                    'r' => { '\r' },
+                    'e' => { '\u{1B}' },
+                    ' ' => { ' ' },
+                    '"' => { '"' },
+                    '/' => { '/' },
+                    '\\' => { '\\' },
+                    'N' => { '\u{85}' },
+                    '_' => { '\u{A0}' },
+                    'L' => { '\u{2028}' },
+                    'P' => { '\u{2029}' },
+                    _ => { return None; },
+                });
+            },
+            c => { value.push(c); },
+        }
+    }
+
+    None
+}
+
+/* The value and length of a single quoted scalar at the start of content */
+fn single_quoted(content: &str) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = content.char_indices().skip(1).peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '\'' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some((_, '\'')) => {
+                value.push('\'');
+                chars.next();
+            },
+            _ => { return Some((value, i + 1)); },
+        }
+    }
+
+    None
+}
@@ -0,0 +1,240 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::sync::OnceLock;
+
+use super::identifiable_scans::{Confidence, ScanDefinition, ScanEngine, ScanOptions, Validation};
+use super::validators::CharClass;
+
+pub const UNCLASSIFIED_32_BYTE_BASE64_RULE_ID: &str = "SEC000/000";
+pub const UNCLASSIFIED_64_BYTE_BASE64_RULE_ID: &str = "SEC000/001";
+pub const UNCLASSIFIED_16_BYTE_HEX_RULE_ID: &str = "SEC000/002";
+
+/*
+ * Ports of the .NET Unclassified32ByteBase64String, Unclassified64Byte-
+ * Base64String and Unclassified16ByteHexadecimalString rules:
+ * (^|[^Base64_\-])[Base64]{43}=
+ * (^|[^Base64_\-])[Base64]{86}==
+ * (^|[^0-9a-f])[0-9a-f]{32}([^0-9a-f]|$)
+ *
+ * These have no signature, so they are checked on every byte. Unlike
+ * .NET, strings that don't look random enough are rejected, see
+ * EntropyThresholds, and matches report their scores as context.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntropyThresholds {
+    min_base64_entropy: f64,
+    min_hex_entropy: f64,
+    min_distribution: f64,
+}
+
+impl EntropyThresholds {
+    /* Minimum Shannon entropy of base64 strings, in bits per character */
+    pub fn with_min_base64_entropy(
+        self,
+        entropy: f64) -> Self {
+        let mut clone = self;
+
+        clone.min_base64_entropy = entropy;
+
+        clone
+    }
+
+    /* Minimum Shannon entropy of hex strings, in bits per character */
+    pub fn with_min_hex_entropy(
+        self,
+        entropy: f64) -> Self {
+        let mut clone = self;
+
+        clone.min_hex_entropy = entropy;
+
+        clone
+    }
+
+    /* Minimum distribution score, see distribution_score() */
+    pub fn with_min_distribution(
+        self,
+        distribution: f64) -> Self {
+        let mut clone = self;
+
+        clone.min_distribution = distribution;
+
+        clone
+    }
+
+    pub fn min_base64_entropy(&self) -> f64 { self.min_base64_entropy }
+
+    pub fn min_hex_entropy(&self) -> f64 { self.min_hex_entropy }
+
+    pub fn min_distribution(&self) -> f64 { self.min_distribution }
+}
+
+impl Default for EntropyThresholds {
+    /*
+     * Random strings of these lengths score around 5 bits for base64, 3.6
+     * bits for hex and a distribution of 1, so they are rarely rejected.
+     */
+    fn default() -> Self {
+        Self {
+            min_base64_entropy: 4.0,
+            min_hex_entropy: 3.0,
+            min_distribution: 0.7,
+        }
+    }
+}
+
+/* Shannon entropy of the bytes in data, in bits per byte */
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+
+    for b in data {
+        counts[*b as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+
+    counts
+        .iter()
+        .filter(|count| **count != 0)
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/*
+ * Distinct bytes in data compared to how many a uniformly random string
+ * of the same length over an alphabet of alphabet_len characters has on
+ * average. Random strings score around 1, repetitive ones much lower.
+ */
+pub fn distribution_score(
+    data: &[u8],
+    alphabet_len: usize) -> f64 {
+    if data.is_empty() || alphabet_len == 0 {
+        return 0.0;
+    }
+
+    let mut seen = [false; 256];
+
+    for b in data {
+        seen[*b as usize] = true;
+    }
+
+    let distinct = seen.iter().filter(|seen| **seen).count() as f64;
+    let alphabet_len = alphabet_len as f64;
+    let expected = alphabet_len * (1.0 - (1.0 - 1.0 / alphabet_len).powi(data.len() as i32));
+
+    distinct / expected
+}
+
+struct UnclassifiedRule {
+    id: &'static str,
+    class: CharClass,
+    alphabet: CharClass,
+    len: usize,
+    padding: &'static [u8],
+    hex: bool,
+}
+
+/* Identifiable keys are reported by their own rules, like .NET */
+fn is_identifiable(
+    id: &str,
+    token: &[u8]) -> bool {
+    static ENGINES: OnceLock<[ScanEngine; 2]> = OnceLock::new();
+
+    let engines = ENGINES.get_or_init(|| [
+        ScanEngine::new(ScanOptions::empty().with_his_v1_32byte()),
+        ScanEngine::new(ScanOptions::empty().with_his_v1_64byte()),
+    ]);
+
+    let engine = match id {
+        UNCLASSIFIED_32_BYTE_BASE64_RULE_ID => { &engines[0] },
+        UNCLASSIFIED_64_BYTE_BASE64_RULE_ID => { &engines[1] },
+        _ => { return false; },
+    };
+
+    !engine.find_matches(token, false).is_empty()
+}
+
+fn validate(
+    rule: &UnclassifiedRule,
+    thresholds: &EntropyThresholds,
+    data: &[u8]) -> Option<Validation> {
+    let token = &data[..rule.len];
+    let len = rule.len + rule.padding.len();
+
+    if data.get(rule.len..len) != Some(rule.padding) || !token.iter().all(|b| rule.alphabet.contains(*b)) {
+        return None;
+    }
+
+    let entropy = shannon_entropy(token);
+    let distribution = distribution_score(token, rule.alphabet.len());
+
+    let min_entropy = match rule.hex {
+        true => { thresholds.min_hex_entropy },
+        false => { thresholds.min_base64_entropy },
+    };
+
+    if entropy < min_entropy || distribution < thresholds.min_distribution {
+        return None;
+    }
+
+    if is_identifiable(rule.id, &data[..len]) {
+        return None;
+    }
+
+    Some(
+        Validation::new(0, len)
+        .with_context("entropy", format!("{:.2}", entropy))
+        .with_context("distribution", format!("{:.2}", distribution)))
+}
+
+/* These are low confidence */
+pub fn scan_definitions(thresholds: EntropyThresholds) -> Vec<ScanDefinition> {
+    let base64 = CharClass::alphanumeric().union(CharClass::bytes(b"+/"));
+    let hex = CharClass::range(b'0', b'9').union(CharClass::range(b'a', b'f'));
+
+    /* Base64 strings must not be part of longer base64 or base64url runs */
+    let all_base64 = CharClass::base64();
+
+    let rules = [
+        UnclassifiedRule {
+            id: UNCLASSIFIED_32_BYTE_BASE64_RULE_ID,
+            class: all_base64,
+            alphabet: base64,
+            len: 43,
+            padding: b"=",
+            hex: false,
+        },
+        UnclassifiedRule {
+            id: UNCLASSIFIED_64_BYTE_BASE64_RULE_ID,
+            class: all_base64,
+            alphabet: base64,
+            len: 86,
+            padding: b"==",
+            hex: false,
+        },
+        UnclassifiedRule {
+            id: UNCLASSIFIED_16_BYTE_HEX_RULE_ID,
+            class: hex,
+            alphabet: hex,
+            len: 32,
+            padding: b"",
+            hex: true,
+        },
+    ];
+
+    rules
+        .into_iter()
+        .map(|rule| {
+            ScanDefinition::unanchored_with_context(
+                rule.id,
+                rule.class,
+                rule.len,
+                rule.padding.len(),
+                move |data| validate(&rule, &thresholds, data))
+            .with_confidence(Confidence::Low)
+        })
+        .collect()
+}
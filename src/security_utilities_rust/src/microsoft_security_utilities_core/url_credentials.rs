@@ -0,0 +1,110 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use super::identifiable_scans::{ScanDefinition, Validation};
+
+pub const URL_CREDENTIALS_RULE_ID: &str = "SEC101/127";
+
+/*
+ * Port of the .NET UrlCredentials rule:
+ * ($|\b)(ftps?|https?):\/\/(?P<refine>[^:@\/]+:[^:@?\/]+)@
+ *
+ * Like .NET, the match is only the user:password credential, the scheme
+ * and host are reported as context. Unlike .NET, the credential can't span
+ * whitespace, which otherwise matches across unrelated text.
+ */
+const SCHEMES: [&[u8]; 4] = [b"https", b"http", b"ftps", b"ftp"];
+
+/* Longest scheme plus the word boundary before it */
+const URL_LOOKBEHIND: usize = 6;
+
+/* Covers "://", the credential, "@" and the host */
+const URL_MAX_LEN: usize = 1024;
+
+fn is_word_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn is_credential_char(
+    b: u8,
+    password: bool) -> bool {
+    match b {
+        b':' | b'@' | b'/' => { false },
+        b'?' => { !password },
+        /*
+         * Printable ASCII and any byte of a UTF8 sequence. UTF16 is validated
+         * one byte per character, so there only Latin-1 characters are.
+         */
+        _ => { b > b' ' && b != 0x7F },
+    }
+}
+
+fn is_host_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' || b == b'%'
+}
+
+fn validate(
+    data: &[u8],
+    anchor: usize) -> Option<Validation> {
+    let scheme = SCHEMES
+        .iter()
+        .find(|scheme| data[..anchor].ends_with(scheme))?;
+
+    let scheme_start = anchor - scheme.len();
+
+    /* Must not be the end of a longer word, such as prefixedhttp */
+    if scheme_start > 0 && is_word_char(data[scheme_start - 1]) {
+        return None;
+    }
+
+    let start = anchor + 3;
+
+    let user_len = data[start..]
+        .iter()
+        .take_while(|b| is_credential_char(**b, false))
+        .count();
+
+    if user_len == 0 || data.get(start + user_len) != Some(&b':') {
+        return None;
+    }
+
+    let password_start = start + user_len + 1;
+
+    let password_len = data[password_start..]
+        .iter()
+        .take_while(|b| is_credential_char(**b, true))
+        .count();
+
+    let at = password_start + password_len;
+
+    if password_len == 0 || data.get(at) != Some(&b'@') {
+        return None;
+    }
+
+    let host_len = data[at + 1..]
+        .iter()
+        .take_while(|b| is_host_char(**b))
+        .count();
+
+    let host = std::str::from_utf8(&data[at + 1..at + 1 + host_len]).ok()?;
+    let scheme = std::str::from_utf8(scheme).ok()?;
+
+    Some(
+        Validation::new(start, at - start)
+        .with_redact(user_len + 1, password_len)
+        .with_context("scheme", scheme)
+        .with_context("host", host))
+}
+
+pub fn scan_definitions() -> Vec<ScanDefinition> {
+    vec![
+        ScanDefinition::with_context(
+            URL_CREDENTIALS_RULE_ID,
+            b"://",
+            b':',
+            3,
+            URL_MAX_LEN,
+            URL_LOOKBEHIND,
+            validate)
+    ]
+}
@@ -0,0 +1,1288 @@
+// Copyright (c) Microsoft. All rights reserved.
+// Licensed under the MIT license. See LICENSE file in the project root for full license information.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use super::identifiable_scans::{write_u32, TableReader};
+
+/*
+ * Declarative validators for scan definitions. A ValidatorSpec is a sequence
+ * of segments anchored at the start of a possible match: runs of a character
+ * class, fixed literals, ranges, and optional or alternative groups. Unlike
+ * closures, specs can be inspected, compared and shipped as configuration.
+ *
+ * The text form is the small, anchored regex subset that the .NET scoped
+ * regexes of the high-performance patterns use:
+ *
+ *   ^              required anchor at the start
+ *   .              any byte (the .NET patterns use Singleline)
+ *   [a-z0-9_\-]    classes with ranges and escapes, \xHH for any byte
+ *   (?:a|bc)       non-capturing groups with alternation
+ *   ? * + {n} {n,} {n,m}
+ *
+ * Specs are compiled into a small program and matched by a backtracking
+ * matcher that never tries the same instruction at the same offset twice,
+ * so matching time is bounded by the program and data length whatever
+ * the pattern, and nothing recurses on the data.
+ */
+
+/* Maximum repetition or length for segments without an upper bound */
+pub const UNBOUNDED: usize = usize::MAX;
+
+/* Limits for parsed patterns, which can come from configuration */
+const MAX_PROGRAM_LEN: usize = 4096;
+const MAX_GROUP_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CharClass {
+    bits: [u64; 4],
+}
+
+impl CharClass {
+    pub const fn empty() -> Self {
+        Self { bits: [0; 4] }
+    }
+
+    pub const fn any() -> Self {
+        Self { bits: [u64::MAX; 4] }
+    }
+
+    pub fn byte(b: u8) -> Self {
+        Self::empty().with(b)
+    }
+
+    pub fn range(
+        first: u8,
+        last: u8) -> Self {
+        let mut class = Self::empty();
+
+        for b in first..=last {
+            class = class.with(b);
+        }
+
+        class
+    }
+
+    pub fn bytes(bytes: &[u8]) -> Self {
+        let mut class = Self::empty();
+
+        for b in bytes {
+            class = class.with(*b);
+        }
+
+        class
+    }
+
+    /* Standard and URL-safe base64 alphabets, without padding */
+    pub fn base64() -> Self {
+        Self::alphanumeric().union(Self::bytes(b"+/-_"))
+    }
+
+    /* RFC 3986 unreserved characters, plus standard base64 */
+    pub fn url_unreserved() -> Self {
+        Self::base64().union(Self::bytes(b"~."))
+    }
+
+    pub fn alphanumeric() -> Self {
+        Self::alphabetic().union(Self::range(b'0', b'9'))
+    }
+
+    pub fn alphabetic() -> Self {
+        Self::range(b'A', b'Z').union(Self::range(b'a', b'z'))
+    }
+
+    pub fn union(
+        self,
+        other: Self) -> Self {
+        let mut bits = self.bits;
+
+        for (bits, other) in bits.iter_mut().zip(other.bits) {
+            *bits |= other;
+        }
+
+        Self { bits }
+    }
+
+    #[inline(always)]
+    pub fn contains(&self, b: u8) -> bool {
+        self.bits[(b >> 6) as usize] & (1 << (b & 63)) != 0
+    }
+
+    pub fn is_any(&self) -> bool { *self == Self::any() }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool { *self == Self::empty() }
+
+    fn with(self, b: u8) -> Self {
+        let mut bits = self.bits;
+        bits[(b >> 6) as usize] |= 1 << (b & 63);
+        Self { bits }
+    }
+
+    fn fmt_byte(
+        f: &mut fmt::Formatter<'_>,
+        b: u8,
+        special: &[u8]) -> fmt::Result {
+        if !b.is_ascii_graphic() {
+            write!(f, "\\x{:02X}", b)
+        } else if special.contains(&b) {
+            write!(f, "\\{}", b as char)
+        } else {
+            write!(f, "{}", b as char)
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_any() {
+            return write!(f, ".");
+        }
+
+        if self.len() == 1 {
+            let b = (0..=255u8).find(|b| self.contains(*b)).unwrap();
+            return Self::fmt_byte(f, b, b"\\.[]()?*+{}|^$");
+        }
+
+        /* Only collapse ranges of digits or letters to keep them readable */
+        let kind = |b: u8| -> u8 {
+            match b {
+                b'0'..=b'9' => { 1 },
+                b'A'..=b'Z' => { 2 },
+                b'a'..=b'z' => { 3 },
+                _ => { 0 },
+            }
+        };
+
+        write!(f, "[")?;
+
+        let mut b = 0usize;
+
+        while b < 256 {
+            if !self.contains(b as u8) {
+                b += 1;
+                continue;
+            }
+
+            let first = b as u8;
+            let mut last = first;
+
+            while kind(first) != 0 &&
+                  last < 255 &&
+                  kind(last + 1) == kind(first) &&
+                  self.contains(last + 1) {
+                last += 1;
+            }
+
+            Self::fmt_byte(f, first, b"\\[]-^")?;
+
+            if last > first {
+                if last > first + 1 {
+                    write!(f, "-")?;
+                }
+
+                Self::fmt_byte(f, last, b"\\[]-^")?;
+            }
+
+            b = last as usize + 1;
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl fmt::Debug for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /* Between min and max bytes of the class */
+    Class {
+        class: CharClass,
+        min: usize,
+        max: usize,
+    },
+
+    /* Between min and max repetitions of any of the alternatives */
+    Group {
+        alternatives: Vec<Vec<Segment>>,
+        min: usize,
+        max: usize,
+    },
+}
+
+/* An instruction of a compiled spec */
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Inst {
+    /* Between min and max bytes of the class, the longest run first */
+    Run {
+        class: CharClass,
+        min: usize,
+        max: usize,
+    },
+
+    /* Tries the first target, then the second */
+    Split(usize, usize),
+
+    Jump(usize),
+
+    Match,
+}
+
+impl Segment {
+    /* Number of instructions compile() emits, saturating */
+    fn program_len(&self) -> usize {
+        match self {
+            Segment::Class { .. } => { 1 },
+            Segment::Group { alternatives, min, max } => {
+                let body = alternatives_program_len(alternatives);
+                let optional = match *max {
+                    UNBOUNDED => { body.saturating_add(2) },
+                    max => { (max - min).saturating_mul(body.saturating_add(1)) },
+                };
+
+                min.saturating_mul(body).saturating_add(optional)
+            },
+        }
+    }
+
+    fn compile(
+        &self,
+        program: &mut Vec<Inst>) {
+        match self {
+            Segment::Class { class, min, max } => {
+                program.push(Inst::Run { class: *class, min: *min, max: *max });
+            },
+            Segment::Group { alternatives, min, max } => {
+                for _ in 0..*min {
+                    compile_alternatives(alternatives, program);
+                }
+
+                if *max == UNBOUNDED {
+                    /* Greedy loop, an empty repetition fails as it's seen before */
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile_alternatives(alternatives, program);
+                    program.push(Inst::Jump(split));
+
+                    let end = program.len();
+                    program[split] = Inst::Split(split + 1, end);
+                    return;
+                }
+
+                /* Each optional repetition skips all of the rest */
+                let mut splits = Vec::new();
+
+                for _ in *min..*max {
+                    splits.push(program.len());
+                    program.push(Inst::Split(program.len() + 1, 0));
+                    compile_alternatives(alternatives, program);
+                }
+
+                let end = program.len();
+
+                for split in splits {
+                    program[split] = Inst::Split(split + 1, end);
+                }
+            },
+        }
+    }
+
+    fn lens(&self) -> (usize, usize) {
+        match self {
+            Segment::Class { min, max, .. } => { (*min, *max) },
+            Segment::Group { alternatives, min, max } => {
+                let mut alt_min = UNBOUNDED;
+                let mut alt_max = 0;
+
+                for alternative in alternatives {
+                    let (seq_min, seq_max) = sequence_lens(alternative);
+                    alt_min = alt_min.min(seq_min);
+                    alt_max = alt_max.max(seq_max);
+                }
+
+                (mul_len(*min, alt_min), mul_len(*max, alt_max))
+            },
+        }
+    }
+
+    fn with_quantifier(
+        self,
+        min: usize,
+        max: usize) -> Self {
+        match self {
+            Segment::Class { class, .. } => { Segment::Class { class, min, max } },
+            Segment::Group { alternatives, .. } => { Segment::Group { alternatives, min, max } },
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (min, max) = match self {
+            Segment::Class { class, min, max } => {
+                write!(f, "{}", class)?;
+                (*min, *max)
+            },
+            Segment::Group { alternatives, min, max } => {
+                write!(f, "(?:")?;
+
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, "|")?;
+                    }
+
+                    for segment in alternative {
+                        write!(f, "{}", segment)?;
+                    }
+                }
+
+                write!(f, ")")?;
+                (*min, *max)
+            },
+        };
+
+        match (min, max) {
+            (1, 1) => { Ok(()) },
+            (0, 1) => { write!(f, "?") },
+            (0, UNBOUNDED) => { write!(f, "*") },
+            (1, UNBOUNDED) => { write!(f, "+") },
+            (min, UNBOUNDED) => { write!(f, "{{{},}}", min) },
+            (min, max) if min == max => { write!(f, "{{{}}}", min) },
+            (min, max) => { write!(f, "{{{},{}}}", min, max) },
+        }
+    }
+}
+
+fn add_len(a: usize, b: usize) -> usize {
+    if a == UNBOUNDED || b == UNBOUNDED {
+        return UNBOUNDED;
+    }
+
+    a.saturating_add(b)
+}
+
+fn mul_len(a: usize, b: usize) -> usize {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+
+    if a == UNBOUNDED || b == UNBOUNDED {
+        return UNBOUNDED;
+    }
+
+    a.saturating_mul(b)
+}
+
+fn sequence_program_len(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .fold(0, |len, segment| len.saturating_add(segment.program_len()))
+}
+
+fn alternatives_program_len(alternatives: &[Vec<Segment>]) -> usize {
+    /* A split and a jump around each alternative but the last */
+    alternatives
+        .iter()
+        .fold(0usize, |len, alternative| len.saturating_add(sequence_program_len(alternative)).saturating_add(2))
+        .saturating_sub(2)
+}
+
+/* The first of the alternatives that leads to a match */
+fn compile_alternatives(
+    alternatives: &[Vec<Segment>],
+    program: &mut Vec<Inst>) {
+    let mut jumps = Vec::new();
+
+    for (i, alternative) in alternatives.iter().enumerate() {
+        let split = program.len();
+        let last = i + 1 == alternatives.len();
+
+        if !last {
+            program.push(Inst::Split(split + 1, 0));
+        }
+
+        for segment in alternative {
+            segment.compile(program);
+        }
+
+        if !last {
+            jumps.push(program.len());
+            program.push(Inst::Jump(0));
+            program[split] = Inst::Split(split + 1, program.len());
+        }
+    }
+
+    let end = program.len();
+
+    for jump in jumps {
+        program[jump] = Inst::Jump(end);
+    }
+}
+
+fn sequence_lens(segments: &[Segment]) -> (usize, usize) {
+    segments
+        .iter()
+        .map(|segment| segment.lens())
+        .fold((0, 0), |(min, max), (seg_min, seg_max)| {
+            (add_len(min, seg_min), add_len(max, seg_max))
+        })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorSpec {
+    segments: Vec<Segment>,
+    min_len: usize,
+    max_len: usize,
+    program: Vec<Inst>,
+}
+
+impl ValidatorSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_segments(segments: Vec<Segment>) -> Self {
+        let (min_len, max_len) = sequence_lens(&segments);
+        let mut program = Vec::new();
+
+        for segment in &segments {
+            segment.compile(&mut program);
+        }
+
+        program.push(Inst::Match);
+
+        Self {
+            segments,
+            min_len,
+            max_len,
+            program,
+        }
+    }
+
+    /* Exactly count bytes of the class */
+    pub fn run(
+        self,
+        class: CharClass,
+        count: usize) -> Self {
+        self.run_between(class, count, count)
+    }
+
+    pub fn run_between(
+        self,
+        class: CharClass,
+        min: usize,
+        max: usize) -> Self {
+        if max < min {
+            panic!("Maximum must not be less than minimum");
+        }
+
+        self.push(Segment::Class { class, min, max })
+    }
+
+    /* Any count bytes, typically the already checked signature */
+    pub fn skip(
+        self,
+        count: usize) -> Self {
+        self.run(CharClass::any(), count)
+    }
+
+    pub fn literal(
+        self,
+        literal: &[u8]) -> Self {
+        let mut clone = self;
+
+        for b in literal {
+            clone = clone.run(CharClass::byte(*b), 1);
+        }
+
+        clone
+    }
+
+    /* A single byte in [first-last] */
+    pub fn range(
+        self,
+        first: u8,
+        last: u8) -> Self {
+        self.run(CharClass::range(first, last), 1)
+    }
+
+    /* A single byte of the set */
+    pub fn one_of(
+        self,
+        bytes: &[u8]) -> Self {
+        self.run(CharClass::bytes(bytes), 1)
+    }
+
+    /* The spec if it matches, such as trailing padding */
+    pub fn optional(
+        self,
+        spec: ValidatorSpec) -> Self {
+        self.push(Segment::Group {
+            alternatives: vec![spec.segments],
+            min: 0,
+            max: 1,
+        })
+    }
+
+    /* The first of the specs that matches */
+    pub fn any_of(
+        self,
+        specs: Vec<ValidatorSpec>) -> Self {
+        self.push(Segment::Group {
+            alternatives: specs.into_iter().map(|spec| spec.segments).collect(),
+            min: 1,
+            max: 1,
+        })
+    }
+
+    pub fn segments(&self) -> &[Segment] { &self.segments }
+
+    pub fn min_len(&self) -> usize { self.min_len }
+
+    /* UNBOUNDED if there is no upper bound */
+    pub fn max_len(&self) -> usize { self.max_len }
+
+    /* Returns the length of the match at the start of data, if any */
+    pub fn match_len(
+        &self,
+        data: &[u8]) -> Option<usize> {
+        if data.len() < self.min_len {
+            return None;
+        }
+
+        let mut visited = Visited::new(self.program.len() * (data.len() + 1));
+        let mut stack = vec![(0, 0)];
+
+        while let Some((pc, pos)) = stack.pop() {
+            /* Tried before and didn't match, or it would have returned */
+            if !visited.insert(pc * (data.len() + 1) + pos) {
+                continue;
+            }
+
+            match &self.program[pc] {
+                Inst::Run { class, min, max } => {
+                    let count = data[pos..]
+                        .iter()
+                        .take(*max)
+                        .take_while(|b| class.contains(**b))
+                        .count();
+
+                    /* Greedy, so the longest run is tried first */
+                    if count >= *min {
+                        stack.extend((*min..=count).map(|count| (pc + 1, pos + count)));
+                    }
+                },
+                Inst::Split(first, second) => {
+                    stack.push((*second, pos));
+                    stack.push((*first, pos));
+                },
+                Inst::Jump(target) => { stack.push((*target, pos)); },
+                Inst::Match => { return Some(pos); },
+            }
+        }
+
+        None
+    }
+
+    fn push(
+        self,
+        segment: Segment) -> Self {
+        let mut segments = self.segments;
+        segments.push(segment);
+        Self::from_segments(segments)
+    }
+
+    /*
+     * Binary form of the segments and the compiled program for serialized
+     * scan engine tables, see ScanEngine::to_bytes. Reading it back skips
+     * parsing and compiling.
+     */
+    pub(crate) fn write_to(
+        &self,
+        out: &mut Vec<u8>) {
+        write_segments(out, &self.segments);
+
+        write_u32(out, self.program.len());
+        for inst in &self.program {
+            match inst {
+                Inst::Run { class, min, max } => {
+                    out.push(0);
+                    write_class(out, class);
+                    write_len(out, *min);
+                    write_len(out, *max);
+                },
+                Inst::Split(first, second) => {
+                    out.push(1);
+                    write_u32(out, *first);
+                    write_u32(out, *second);
+                },
+                Inst::Jump(target) => {
+                    out.push(2);
+                    write_u32(out, *target);
+                },
+                Inst::Match => { out.push(3); },
+            }
+        }
+    }
+
+    /*
+     * Reads what write_to() wrote. The program is checked to stay within
+     * itself and the limits of parsed patterns, the tables could be corrupt.
+     */
+    pub(crate) fn read_from(reader: &mut TableReader) -> io::Result<Self> {
+        let segments = read_segments(reader, 0)?;
+        let (min_len, max_len) = sequence_lens(&segments);
+
+        let len = reader.u32()? as usize;
+
+        if len == 0 || len > MAX_PROGRAM_LEN {
+            return Err(TableReader::invalid(format!("Validator program has {} instructions", len)));
+        }
+
+        let target = |reader: &mut TableReader| -> io::Result<usize> {
+            match reader.u32()? as usize {
+                target if target < len => { Ok(target) },
+                target => { Err(TableReader::invalid(format!("Validator program jumps to {}", target))) },
+            }
+        };
+
+        let mut program = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            let inst = match reader.u8()? {
+                0 => {
+                    let class = read_class(reader)?;
+                    let (min, max) = read_quantifier(reader)?;
+                    Inst::Run { class, min, max }
+                },
+                1 => { Inst::Split(target(reader)?, target(reader)?) },
+                2 => { Inst::Jump(target(reader)?) },
+                3 => { Inst::Match },
+                _ => { return Err(TableReader::invalid("Unknown validator instruction".to_string())); },
+            };
+
+            program.push(inst);
+        }
+
+        if program.last() != Some(&Inst::Match) {
+            return Err(TableReader::invalid("Validator program does not end in a match".to_string()));
+        }
+
+        Ok(Self {
+            segments,
+            min_len,
+            max_len,
+            program,
+        })
+    }
+}
+
+fn write_len(
+    out: &mut Vec<u8>,
+    len: usize) {
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn write_class(
+    out: &mut Vec<u8>,
+    class: &CharClass) {
+    for bits in class.bits {
+        out.extend_from_slice(&bits.to_le_bytes());
+    }
+}
+
+fn write_segments(
+    out: &mut Vec<u8>,
+    segments: &[Segment]) {
+    write_u32(out, segments.len());
+
+    for segment in segments {
+        match segment {
+            Segment::Class { class, min, max } => {
+                out.push(0);
+                write_class(out, class);
+                write_len(out, *min);
+                write_len(out, *max);
+            },
+            Segment::Group { alternatives, min, max } => {
+                out.push(1);
+                write_u32(out, alternatives.len());
+                for alternative in alternatives {
+                    write_segments(out, alternative);
+                }
+                write_len(out, *min);
+                write_len(out, *max);
+            },
+        }
+    }
+}
+
+/* Lengths are written as u64, UNBOUNDED whatever the size of usize */
+fn read_len(reader: &mut TableReader) -> io::Result<usize> {
+    match reader.u64()? {
+        u64::MAX => { Ok(UNBOUNDED) },
+        len => {
+            usize::try_from(len)
+                .map_err(|_| TableReader::invalid(format!("Validator length {} is too large", len)))
+        },
+    }
+}
+
+fn read_quantifier(reader: &mut TableReader) -> io::Result<(usize, usize)> {
+    let min = read_len(reader)?;
+    let max = read_len(reader)?;
+
+    if min > max || min == UNBOUNDED {
+        return Err(TableReader::invalid(format!("Validator repeats between {} and {} times", min, max)));
+    }
+
+    Ok((min, max))
+}
+
+fn read_class(reader: &mut TableReader) -> io::Result<CharClass> {
+    let mut bits = [0; 4];
+
+    for bits in bits.iter_mut() {
+        *bits = reader.u64()?;
+    }
+
+    Ok(CharClass { bits })
+}
+
+fn read_segments(
+    reader: &mut TableReader,
+    depth: usize) -> io::Result<Vec<Segment>> {
+    let count = reader.u32()? as usize;
+    let mut segments = Vec::with_capacity(count.min(MAX_PROGRAM_LEN));
+
+    for _ in 0..count {
+        let segment = match reader.u8()? {
+            0 => {
+                let class = read_class(reader)?;
+                let (min, max) = read_quantifier(reader)?;
+                Segment::Class { class, min, max }
+            },
+            1 => {
+                if depth == MAX_GROUP_DEPTH {
+                    return Err(TableReader::invalid(format!("Validator groups nest deeper than {}", MAX_GROUP_DEPTH)));
+                }
+
+                let count = reader.u32()? as usize;
+                let mut alternatives = Vec::with_capacity(count.min(MAX_PROGRAM_LEN));
+
+                for _ in 0..count {
+                    alternatives.push(read_segments(reader, depth + 1)?);
+                }
+
+                let (min, max) = read_quantifier(reader)?;
+                Segment::Group { alternatives, min, max }
+            },
+            _ => { return Err(TableReader::invalid("Unknown validator segment".to_string())); },
+        };
+
+        segments.push(segment);
+    }
+
+    Ok(segments)
+}
+
+/*
+ * Which instructions were tried at which offsets. Large programs on large
+ * data only track what was tried, rather than everything that could be.
+ */
+enum Visited {
+    Bits(Vec<u64>),
+    Set(std::collections::HashSet<usize>),
+}
+
+impl Visited {
+    const MAX_BITS: usize = 1 << 20;
+
+    fn new(len: usize) -> Self {
+        match len <= Self::MAX_BITS {
+            true => { Visited::Bits(vec![0; len.div_ceil(64)]) },
+            false => { Visited::Set(Default::default()) },
+        }
+    }
+
+    /* Whether state wasn't visited before */
+    fn insert(
+        &mut self,
+        state: usize) -> bool {
+        match self {
+            Visited::Bits(bits) => {
+                let seen = bits[state / 64] & (1 << (state % 64)) != 0;
+                bits[state / 64] |= 1 << (state % 64);
+                !seen
+            },
+            Visited::Set(set) => { set.insert(state) },
+        }
+    }
+}
+
+impl Default for ValidatorSpec {
+    fn default() -> Self {
+        Self::from_segments(Vec::new())
+    }
+}
+
+impl fmt::Display for ValidatorSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "^")?;
+
+        for segment in &self.segments {
+            write!(f, "{}", segment)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseValidatorError {
+    message: String,
+}
+
+impl ParseValidatorError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for ParseValidatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseValidatorError {}
+
+impl FromStr for ValidatorSpec {
+    type Err = ParseValidatorError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let bytes = pattern.as_bytes();
+
+        if bytes.first() != Some(&b'^') {
+            return Err(ParseValidatorError::new(format!(
+                "The pattern must be anchored to the beginning of the input: '{}'",
+                pattern)));
+        }
+
+        let mut parser = Parser {
+            pattern: bytes,
+            pos: 1,
+            depth: 0,
+        };
+
+        let mut alternatives = parser.parse_alternatives()?;
+
+        if parser.pos != bytes.len() {
+            return Err(ParseValidatorError::new(format!(
+                "Unexpected '{}' at offset {} in '{}'",
+                bytes[parser.pos] as char,
+                parser.pos,
+                pattern)));
+        }
+
+        let segments = match alternatives.len() {
+            1 => { alternatives.pop().unwrap() },
+            _ => {
+                vec![Segment::Group {
+                    alternatives,
+                    min: 1,
+                    max: 1,
+                }]
+            },
+        };
+
+        /* Compiling repeats groups, which could take any amount of memory */
+        if sequence_program_len(&segments) >= MAX_PROGRAM_LEN {
+            return Err(ParseValidatorError::new(format!(
+                "The pattern repeats groups too often, it compiles to more than {} instructions: '{}'",
+                MAX_PROGRAM_LEN,
+                pattern)));
+        }
+
+        Ok(Self::from_segments(segments))
+    }
+}
+
+struct Parser<'a> {
+    pattern: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(
+        &self,
+        message: &str) -> ParseValidatorError {
+        ParseValidatorError::new(format!("{} at offset {}", message, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, ParseValidatorError> {
+        match self.peek() {
+            Some(b) => {
+                self.pos += 1;
+                Ok(b)
+            },
+            None => { Err(self.error("Unexpected end of pattern")) },
+        }
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Segment>>, ParseValidatorError> {
+        let mut alternatives = vec![self.parse_sequence()?];
+
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Segment>, ParseValidatorError> {
+        let mut segments = Vec::new();
+
+        while let Some(b) = self.peek() {
+            let class = match b {
+                b'|' | b')' => { break; },
+                b'(' => {
+                    let group = self.parse_group()?;
+                    let (min, max) = self.parse_quantifier()?;
+                    segments.push(group.with_quantifier(min, max));
+                    continue;
+                },
+                b'[' => { self.parse_class()? },
+                b'.' => {
+                    self.pos += 1;
+                    CharClass::any()
+                },
+                b'\\' => {
+                    self.pos += 1;
+                    CharClass::byte(self.parse_escape()?)
+                },
+                b'^' | b'$' | b'*' | b'+' | b'?' | b'{' => {
+                    return Err(self.error(&format!("Unsupported '{}'", b as char)));
+                },
+                _ => {
+                    self.pos += 1;
+                    CharClass::byte(b)
+                },
+            };
+
+            let (min, max) = self.parse_quantifier()?;
+
+            segments.push(Segment::Class { class, min, max });
+        }
+
+        Ok(segments)
+    }
+
+    fn parse_group(&mut self) -> Result<Segment, ParseValidatorError> {
+        /* Only non-capturing groups, nothing is ever captured */
+        if !self.pattern[self.pos..].starts_with(b"(?:") {
+            return Err(self.error("Only non-capturing groups are supported"));
+        }
+
+        if self.depth == MAX_GROUP_DEPTH {
+            return Err(self.error(&format!("Groups nest deeper than {}", MAX_GROUP_DEPTH)));
+        }
+
+        self.pos += 3;
+        self.depth += 1;
+
+        let alternatives = self.parse_alternatives()?;
+
+        self.depth -= 1;
+
+        if self.next()? != b')' {
+            return Err(self.error("Unterminated group"));
+        }
+
+        Ok(Segment::Group {
+            alternatives,
+            min: 1,
+            max: 1,
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<CharClass, ParseValidatorError> {
+        /* Skip the '[' */
+        self.pos += 1;
+
+        if self.peek() == Some(b'^') {
+            return Err(self.error("Negated classes are not supported"));
+        }
+
+        let mut class = CharClass::empty();
+
+        loop {
+            let first = match self.next()? {
+                b']' => { break; },
+                b'\\' => { self.parse_escape()? },
+                b => { b },
+            };
+
+            /* A '-' right before the ']' is a literal */
+            if self.peek() == Some(b'-') &&
+               self.pattern.get(self.pos + 1) != Some(&b']') {
+                self.pos += 1;
+
+                let last = match self.next()? {
+                    b'\\' => { self.parse_escape()? },
+                    b => { b },
+                };
+
+                if last < first {
+                    return Err(self.error("Invalid range"));
+                }
+
+                class = class.union(CharClass::range(first, last));
+            } else {
+                class = class.union(CharClass::byte(first));
+            }
+        }
+
+        Ok(class)
+    }
+
+    fn parse_escape(&mut self) -> Result<u8, ParseValidatorError> {
+        let b = self.next()?;
+
+        if b == b'x' {
+            let start = self.pos;
+
+            if self.pattern.len() < start + 2 {
+                return Err(self.error("Expected two hex digits"));
+            }
+
+            let hex = std::str::from_utf8(&self.pattern[start..start + 2])
+                .map_err(|_| self.error("Expected two hex digits"))?;
+
+            self.pos += 2;
+
+            return u8::from_str_radix(hex, 16)
+                .map_err(|_| self.error("Expected two hex digits"));
+        }
+
+        /* Only escaped punctuation, no shorthand classes */
+        if b.is_ascii_alphanumeric() {
+            return Err(self.error(&format!("Unsupported escape '\\{}'", b as char)));
+        }
+
+        Ok(b)
+    }
+
+    fn parse_quantifier(&mut self) -> Result<(usize, usize), ParseValidatorError> {
+        let quantifier = match self.peek() {
+            Some(b'?') => { (0, 1) },
+            Some(b'*') => { (0, UNBOUNDED) },
+            Some(b'+') => { (1, UNBOUNDED) },
+            Some(b'{') => {
+                self.pos += 1;
+
+                let min = self.parse_number()?;
+                let max = match self.next()? {
+                    b'}' => { return Ok((min, min)); },
+                    b',' => {
+                        match self.peek() {
+                            Some(b'}') => { UNBOUNDED },
+                            _ => { self.parse_number()? },
+                        }
+                    },
+                    _ => { return Err(self.error("Invalid quantifier")); },
+                };
+
+                if self.next()? != b'}' || max < min {
+                    return Err(self.error("Invalid quantifier"));
+                }
+
+                return Ok((min, max));
+            },
+            _ => { return Ok((1, 1)); },
+        };
+
+        self.pos += 1;
+
+        Ok(quantifier)
+    }
+
+    fn parse_number(&mut self) -> Result<usize, ParseValidatorError> {
+        let start = self.pos;
+
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.pattern[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| self.error("Expected a number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validator_spec_match_len() {
+        let cases = [
+            ("^abc", "abcd", Some(3)),
+            ("^abc", "abd", None),
+            ("^.{3}[a-z2-7]{3}", "oy2ab7", Some(6)),
+            ("^.{3}[a-z2-7]{3}", "oy2ab8", None),
+            ("^.{4}[0-9a-f\\-]{2,4}", "dapi0-", Some(6)),
+            ("^.{4}[0-9a-f\\-]{2,4}", "dapi0-a1ff", Some(8)),
+            ("^.{4}[0-9a-f\\-]{2,4}", "dapi0", None),
+            ("^[a-z]{2}(?:9|D|H)", "abD", Some(3)),
+            ("^[a-z]{2}(?:9|D|H)", "abE", None),
+            ("^[a-z]{2}(?:[0-9]{2}==)?", "ab12==", Some(6)),
+            ("^[a-z]{2}(?:[0-9]{2}==)?", "ab12=", Some(2)),
+            ("^[A-P]=?", "P=", Some(2)),
+            ("^[A-P]=?", "Q=", None),
+            ("^[~.a-z_\\-]+", "a~.-_b!", Some(6)),
+            ("^a*ab", "aaab", Some(4)),
+            ("^[a-z]{1,}b", "aaab", Some(4)),
+            ("^(?:ab|a)c", "ac", Some(2)),
+            ("^\\x41\\x2B", "A+", Some(2)),
+        ];
+
+        for (i, (pattern, data, expected)) in cases.iter().enumerate() {
+            let spec: ValidatorSpec = pattern.parse().unwrap();
+            assert_eq!(*expected, spec.match_len(data.as_bytes()), "Case {}: Match", i);
+
+            /* Text form round trips */
+            let reparsed: ValidatorSpec = spec.to_string().parse().unwrap();
+            assert_eq!(spec, reparsed, "Case {}: Round Trip {}", i, spec);
+
+            /* So does the binary form */
+            let mut out = Vec::new();
+            spec.write_to(&mut out);
+            let read = ValidatorSpec::read_from(&mut TableReader::new(&out)).unwrap();
+            assert_eq!(spec, read, "Case {}: Binary Round Trip {}", i, spec);
+        }
+    }
+
+    #[test]
+    fn validator_spec_binary_corrupt() {
+        let spec: ValidatorSpec = "^(?:ab|a)c".parse().unwrap();
+        let mut out = Vec::new();
+        spec.write_to(&mut out);
+
+        /* The program starts after the segments, the first is a split */
+        let mut segments = Vec::new();
+        write_segments(&mut segments, spec.segments());
+        let program = segments.len();
+
+        let cases = [
+            (program, 0u32, "instructions"),
+            (program, MAX_PROGRAM_LEN as u32 + 1, "instructions"),
+            (program + 5, 100, "jumps"),
+        ];
+
+        for (offset, value, expected) in cases {
+            let mut corrupt = out.clone();
+            corrupt[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+
+            let err = ValidatorSpec::read_from(&mut TableReader::new(&corrupt)).err().unwrap();
+            assert!(err.to_string().contains(expected), "Offset {}: {}", offset, err);
+        }
+
+        /* Truncated */
+        assert!(ValidatorSpec::read_from(&mut TableReader::new(&out[..out.len() - 1])).is_err());
+    }
+
+    #[test]
+    fn validator_spec_unsupported() {
+        let cases = [
+            "abc",
+            "^(abc)",
+            "^[^a]",
+            "^\\d",
+            "^a{2",
+            "^a{3,2}",
+            "^a)",
+            "^[b-a]",
+            "^a$",
+            "^\\xZZ",
+        ];
+
+        for (i, pattern) in cases.iter().enumerate() {
+            assert!(pattern.parse::<ValidatorSpec>().is_err(), "Case {}: {}", i, pattern);
+        }
+    }
+
+    #[test]
+    fn validator_spec_pathological() {
+        /* Ambiguous alternatives don't backtrack exponentially */
+        let data = [b'a'; 64];
+        let spec: ValidatorSpec = "^(?:a|a){0,40}b".parse().unwrap();
+        assert_eq!(None, spec.match_len(&data));
+
+        let spec: ValidatorSpec = "^(?:a|aa|a*)*b".parse().unwrap();
+        assert_eq!(None, spec.match_len(&data));
+        assert_eq!(Some(65), spec.match_len(&[&data[..], b"b"].concat()));
+
+        /* Repetitions don't recurse, however many there are */
+        let spec = ValidatorSpec::from_segments(vec![Segment::Group {
+            alternatives: vec![vec![Segment::Class { class: CharClass::byte(b'a'), min: 1, max: 1 }]],
+            min: 0,
+            max: 60000,
+        }]);
+        assert_eq!(Some(60000), spec.match_len(&vec![b'a'; 60001]));
+
+        /* Parsed patterns are limited, they can come from configuration */
+        let err = "^(?:a){0,60000}".parse::<ValidatorSpec>().unwrap_err();
+        assert!(err.to_string().contains("instructions"), "{}", err);
+
+        let nested = format!("^{}a{}", "(?:".repeat(17), ")".repeat(17));
+        let err = nested.parse::<ValidatorSpec>().unwrap_err();
+        assert!(err.to_string().contains("nest"), "{}", err);
+    }
+
+    #[test]
+    fn validator_spec_builder() {
+        let spec = ValidatorSpec::new()
+            .run(CharClass::base64(), 33)
+            .skip(4)
+            .range(b'A', b'P')
+            .run(CharClass::base64(), 5)
+            .optional(ValidatorSpec::new().literal(b"="));
+
+        assert_eq!("^[+\\-/0-9A-Z_a-z]{33}.{4}[A-P][+\\-/0-9A-Z_a-z]{5}(?:=)?", spec.to_string());
+        assert_eq!(43, spec.min_len());
+        assert_eq!(44, spec.max_len());
+        assert_eq!(5, spec.segments().len());
+
+        let parsed: ValidatorSpec = "^[a-zA-Z0-9+/_\\-]{33}.{4}[A-P][a-zA-Z0-9+/_\\-]{5}(?:=)?".parse().unwrap();
+        assert_eq!(spec, parsed);
+
+        let spec = ValidatorSpec::new()
+            .literal(b"JQQJ9")
+            .any_of(vec![
+                ValidatorSpec::new().literal(b"9"),
+                ValidatorSpec::new().literal(b"D")])
+            .run_between(CharClass::alphanumeric(), 1, UNBOUNDED);
+
+        assert_eq!("^JQQJ9(?:9|D)[0-9A-Za-z]+", spec.to_string());
+        assert_eq!(7, spec.min_len());
+        assert_eq!(UNBOUNDED, spec.max_len());
+        assert_eq!(Some(8), spec.match_len(b"JQQJ9Dab!"));
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(test)]
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use crate::microsoft_security_utilities_core::identifiable_scans::{Confidence, ScanEngine, ScanOptions};
+use crate::microsoft_security_utilities_core::pkcs12::PKCS12_RULE_ID;
+use crate::microsoft_security_utilities_core::redaction;
+
+/* The start of a PFX exported by openssl, without any key material */
+const OPENSSL_PREFIX: &str = "MIIGjwIBAzCCBkUGCSqGSIb3DQEHAaCCBjYEggYy";
+
+/// Builds the base64 of a PFX whose authSafe has a content type and some filler.
+fn pfx(
+    version: u8,
+    content_type: u8,
+    filler: usize) -> String {
+    let oid = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, content_type];
+
+    let mut content_info = vec![0x06, 0x09];
+    content_info.extend_from_slice(&oid);
+    content_info.extend((0..filler).map(|i| i as u8));
+
+    let mut body = vec![0x02, 0x01, version, 0x30, 0x82];
+    body.extend_from_slice(&(content_info.len() as u16).to_be_bytes());
+    body.extend(content_info);
+
+    let mut der = vec![0x30, 0x82];
+    der.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    der.extend(body);
+
+    STANDARD.encode(der)
+}
+
+/// Wraps base64 at a column with a line break, as base64 and certutil do.
+fn wrap(
+    base64: &str,
+    columns: usize,
+    line_break: &str) -> String {
+    base64
+        .as_bytes()
+        .chunks(columns)
+        .map(|line| std::str::from_utf8(line).unwrap())
+        .collect::<Vec<_>>()
+        .join(line_break)
+}
+
+#[test]
+fn pkcs12_scan() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_pkcs12());
+
+    let data_pfx = pfx(3, 1, 1000);
+    let signed_pfx = pfx(3, 2, 998);
+    let unpadded_pfx = pfx(3, 1, 998);
+    let large_pfx = pfx(3, 1, 60000);
+
+    assert!(data_pfx.ends_with('='));
+    assert!(!unpadded_pfx.ends_with('='));
+    assert!(large_pfx.len() > 64 * 1024);
+
+    let lf_pfx = wrap(&data_pfx, 64, "\n");
+    let crlf_pfx = wrap(&unpadded_pfx, 76, "\r\n");
+    let escaped_pfx = wrap(&large_pfx, 64, "\\r\\n");
+
+    let cases = [
+        (data_pfx.clone(), Some((data_pfx.as_str(), "data"))),
+        (signed_pfx.clone(), Some((signed_pfx.as_str(), "signedData"))),
+        (format!("some padding data {} more padding data", data_pfx), Some((data_pfx.as_str(), "data"))),
+        (format!("\"pfx\": \"{}\"", large_pfx), Some((large_pfx.as_str(), "data"))),
+        // The blob ends where the DER does, even if base64 continues.
+        (format!("{}AAAAAAAA", unpadded_pfx), Some((unpadded_pfx.as_str(), "data"))),
+        // Wrapped blobs match across their line breaks, escaped ones too.
+        (format!("{}\n", lf_pfx), Some((lf_pfx.as_str(), "data"))),
+        (format!("{}\r\nAAAA", crlf_pfx), Some((crlf_pfx.as_str(), "data"))),
+        (format!("\"pfx\": \"{}\\r\\n\"", escaped_pfx), Some((escaped_pfx.as_str(), "data"))),
+        (format!("{}\n\n{}", &data_pfx[..32], &data_pfx[32..]), Some((&data_pfx[..32], "data"))),
+        // Truncated blobs match up to where the base64 ends.
+        (format!("{}...", OPENSSL_PREFIX), Some((OPENSSL_PREFIX, "data"))),
+        (data_pfx[..32].to_string(), Some((&data_pfx[..32], "data"))),
+        // Too short to check the content type, .NET matches these.
+        (data_pfx[..31].to_string(), None),
+        // Not a PFX.
+        (pfx(2, 1, 1000), None),
+        (pfx(3, 3, 1000), None),
+        (OPENSSL_PREFIX.replace("DQEH", "DQEB"), None),
+    ];
+
+    for (i, (case, expected)) in cases.iter().enumerate() {
+        let matches = engine.find_matches(case.as_bytes(), true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "Case {}", i);
+
+        if let Some((blob, content_type)) = expected {
+            let m = &matches[0];
+            assert_eq!(PKCS12_RULE_ID, m.name(), "Case {}: Id", i);
+            assert_eq!(Confidence::Medium, m.confidence(), "Case {}: Confidence", i);
+            assert_eq!(case.find(blob).unwrap() as u64, m.start(), "Case {}: Start", i);
+            assert_eq!(blob.len() as u64, m.len(), "Case {}: Len", i);
+            assert_eq!(Some(*content_type), m.context_value("content_type"), "Case {}: Content Type", i);
+        }
+
+        // UTF16 LE
+        let utf16: Vec<u8> = case.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let matches = engine.find_matches(&utf16, false);
+        assert_eq!(expected.is_some() as usize, matches.len(), "UTF16 Case {}", i);
+
+        if let Some((blob, _)) = expected {
+            assert_eq!(blob.len() as u64 * 2, matches[0].len(), "UTF16 Case {}: Len", i);
+        }
+    }
+}
+
+#[test]
+fn pkcs12_redact() {
+    let engine = ScanEngine::new(ScanOptions::empty().with_pkcs12());
+
+    assert_eq!(
+        "<pfx>+++</pfx>",
+        redaction::mask(&engine, &format!("<pfx>{}</pfx>", pfx(3, 1, 5000))));
+}
@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use std::sync::Arc;
+
+use crate::microsoft_security_utilities_core::identifiable_scans::ScanOptions;
+use crate::microsoft_security_utilities_core::scan_engine_handle::ScanEngineHandle;
+
+const KEY: &str = "HHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHHJQQJ99AEAAAAAAAAAAAAAAAAAZFU03Ml";
+
+#[test]
+fn scan_engine_handle_swap() {
+    let handle = ScanEngineHandle::from_options(ScanOptions::empty().with_his_v2());
+    assert_eq!(0, handle.generation());
+
+    // Start a scan, then swap the rules halfway through the data.
+    let mut scan = handle.scan();
+    scan.parse_bytes(&KEY.as_bytes()[..40]);
+
+    let previous = handle.swap_options(ScanOptions::empty().with_aad());
+    assert_eq!(1, handle.generation());
+    assert!(Arc::ptr_eq(&previous, scan.engine()));
+
+    // The scan in progress finishes against the rules it started with.
+    scan.parse_bytes(&KEY.as_bytes()[40..]);
+    assert_eq!(0, scan.generation());
+    assert_eq!(1, scan.possible_matches().len());
+    assert_eq!("SEC101/200", scan.possible_matches()[0].matches_bytes(KEY.as_bytes(), false).unwrap().name());
+
+    // Resetting keeps the pinned rules.
+    scan.reset();
+    scan.parse_bytes(KEY.as_bytes());
+    assert!(scan.has_possible_matches());
+
+    // New scans pick up the new rules.
+    let mut scan = handle.scan();
+    scan.parse_bytes(KEY.as_bytes());
+    assert_eq!(1, scan.generation());
+    assert!(!scan.has_possible_matches());
+    assert!(Arc::ptr_eq(&handle.snapshot(), scan.engine()));
+}
+
+#[test]
+fn scan_engine_handle_threads() {
+    let handle = Arc::new(ScanEngineHandle::default());
+
+    let scanners: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    let mut scan = handle.scan();
+                    scan.parse_bytes(KEY.as_bytes());
+
+                    // Either the default rules or the swapped in v2 only rules find it.
+                    assert_eq!(1, scan.possible_matches().len());
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..20 {
+        handle.swap_options(ScanOptions::empty().with_his_v2());
+    }
+
+    for scanner in scanners {
+        scanner.join().unwrap();
+    }
+
+    assert_eq!(20, handle.generation());
+}
@@ -0,0 +1,149 @@
+#![cfg(test)]
+
+use std::io::Cursor;
+
+use crate::microsoft_security_utilities_core::identifiable_scans::{Confidence, ScanEngine, ScanOptions, ScanState};
+use crate::microsoft_security_utilities_core::unclassified::{self, EntropyThresholds, UNCLASSIFIED_16_BYTE_HEX_RULE_ID, UNCLASSIFIED_32_BYTE_BASE64_RULE_ID, UNCLASSIFIED_64_BYTE_BASE64_RULE_ID};
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const HEX: &[u8] = b"0123456789abcdef";
+
+/// Random looking characters from alphabet, the same for the same seed.
+fn random_string(
+    alphabet: &[u8],
+    len: usize,
+    seed: u64) -> String {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            alphabet[(state >> 32) as usize % alphabet.len()] as char
+        })
+        .collect()
+}
+
+fn default_engine() -> ScanEngine {
+    ScanEngine::new(ScanOptions::empty().with_unclassified(EntropyThresholds::default()))
+}
+
+#[test]
+fn unclassified_scan() {
+    let engine = default_engine();
+
+    let base64_32 = format!("{}=", random_string(BASE64, 43, 1));
+    let base64_64 = format!("{}==", random_string(BASE64, 86, 2));
+    let hex = random_string(HEX, 32, 3);
+
+    let cases = [
+        (format!("key: {}\n", base64_32), Some((UNCLASSIFIED_32_BYTE_BASE64_RULE_ID, base64_32.as_str()))),
+        (format!("\"{}\"", base64_64), Some((UNCLASSIFIED_64_BYTE_BASE64_RULE_ID, base64_64.as_str()))),
+        (format!("secret={}", hex), Some((UNCLASSIFIED_16_BYTE_HEX_RULE_ID, hex.as_str()))),
+        // Part of longer strings, or without the padding.
+        (format!("a{}", base64_32), None),
+        (format!("_{}", base64_32), None),
+        (base64_32[..43].to_string(), None),
+        (format!("{}1", hex), None),
+        (format!("f{}", hex), None),
+        // Base64url and upper case hex.
+        (format!("{}-{}=", &base64_32[..20], &base64_32[21..43]), None),
+        (hex.to_uppercase(), None),
+        // Not random enough.
+        (format!("{}=", "a".repeat(43)), None),
+        (format!("{}=", &"abcdefgh".repeat(6)[..43]), None),
+        ("deadbeef".repeat(4), None),
+        ("00000000000000000000000000000000".to_string(), None),
+    ];
+
+    for (i, (data, expected)) in cases.iter().enumerate() {
+        let matches = engine.find_matches(data.as_bytes(), true);
+        assert_eq!(expected.is_some() as usize, matches.len(), "Case {}", i);
+
+        if let Some((id, text)) = expected {
+            let m = &matches[0];
+            assert_eq!(*id, m.name(), "Case {}: Id", i);
+            assert_eq!(*text, m.text(), "Case {}: Text", i);
+            assert_eq!(data.find(text).unwrap() as u64, m.start(), "Case {}: Start", i);
+            assert_eq!(Confidence::Low, m.confidence(), "Case {}: Confidence", i);
+            assert!(m.context_value("entropy").is_some(), "Case {}: Entropy", i);
+            assert!(m.context_value("distribution").is_some(), "Case {}: Distribution", i);
+        }
+
+        // UTF16 LE
+        let utf16: Vec<u8> = data.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let matches = engine.find_matches(&utf16, false);
+        assert_eq!(expected.is_some() as usize, matches.len(), "UTF16 Case {}", i);
+    }
+}
+
+#[test]
+fn unclassified_skips_identifiable_keys() {
+    let thresholds = EntropyThresholds::default()
+        .with_min_base64_entropy(0.0)
+        .with_min_distribution(0.0);
+
+    let engine = ScanEngine::new(ScanOptions::empty().with_unclassified(thresholds));
+
+    for key in [
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa+AEhG2s/8w=",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa+ABa13FZVQ==",
+    ] {
+        assert!(engine.find_matches(key.as_bytes(), false).is_empty(), "{}", key);
+    }
+
+    // With the thresholds lowered, repetitive strings are reported.
+    assert_eq!(1, engine.find_matches(format!("{}=", "a".repeat(43)).as_bytes(), false).len());
+}
+
+#[test]
+fn unclassified_thresholds() {
+    let data = format!("{}=", random_string(BASE64, 43, 4));
+
+    let strict = EntropyThresholds::default().with_min_base64_entropy(8.0);
+    let engine = ScanEngine::new(ScanOptions::empty().with_unclassified(strict));
+    assert!(engine.find_matches(data.as_bytes(), false).is_empty());
+
+    let strict = EntropyThresholds::default().with_min_distribution(1.5);
+    let engine = ScanEngine::new(ScanOptions::empty().with_unclassified(strict));
+    assert!(engine.find_matches(data.as_bytes(), false).is_empty());
+
+    assert_eq!(1, default_engine().find_matches(data.as_bytes(), false).len());
+}
+
+#[test]
+fn unclassified_truncated_window() {
+    let engine = default_engine();
+    let data = format!("{}=", random_string(BASE64, 43, 6));
+    let data = data.as_bytes();
+
+    let mut state = ScanState::default();
+    engine.parse_bytes(&mut state, data);
+
+    let check = state
+        .possible_matches()
+        .iter()
+        .find(|check| check.matches_bytes(data, false).is_some())
+        .expect("Full window matches");
+
+    // Less than the whole token, like the end of a reader, doesn't match
+    for len in 0..data.len() {
+        assert!(check.matches_bytes(&data[..len], false).is_none(), "Len {}", len);
+
+        let mut buf = vec![0; 128];
+        let matched = check.matches_reader(&mut Cursor::new(&data[..len]), &mut buf, false).unwrap();
+        assert!(matched.is_none(), "Reader Len {}", len);
+    }
+}
+
+#[test]
+fn unclassified_scores() {
+    assert_eq!(0.0, unclassified::shannon_entropy(b"aaaa"));
+    assert_eq!(2.0, unclassified::shannon_entropy(b"abcd"));
+    assert_eq!(0.0, unclassified::distribution_score(b"", 64));
+
+    let random = random_string(BASE64, 43, 5);
+    assert!(unclassified::distribution_score(random.as_bytes(), 64) > 0.8);
+    assert!(unclassified::distribution_score(b"abababababababababababababababababababababa", 64) < 0.1);
+}